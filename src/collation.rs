@@ -0,0 +1,134 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+//! Sort strings in the order of the Vietnamese alphabet: letters first
+//! (a ă â b c d đ e ê ... o ô ơ ... u ư v x y), then tones, then case.
+//! Eg: "da" < "dà" < "đa" < "e", while `str` puts "đ" after "z".
+
+use std::cmp::Ordering;
+use vword::{ VChar, Tone };
+
+// The letters in order, with the Latin letters which are not Vietnamese
+// where they are in the Latin alphabet.
+const ALPHABET: [char; 33] = [
+    'a', 'ă', 'â', 'b', 'c', 'd', 'đ', 'e', 'ê', 'f', 'g', 'h', 'i', 'j', 'k',
+    'l', 'm', 'n', 'o', 'ô', 'ơ', 'p', 'q', 'r', 's', 't', 'u', 'ư', 'v', 'w',
+    'x', 'y', 'z',
+];
+
+/// Compare strings by letters, then by tones, then by case (lowercase
+/// first). Characters other than letters come before the letters if they
+/// are ASCII (eg: spaces, digits), after them if not.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Collator {
+    tone_order: [Tone; 6],
+}
+
+impl Collator {
+/// Return a collator with the tones in the order of dictionaries:
+/// none, huyền, hỏi, ngã, sắc, nặng.
+    pub fn new() -> Collator {
+        Collator::with_tone_order([Tone::N, Tone::F, Tone::R, Tone::X, Tone::S, Tone::J])
+    }
+/// Return a collator with the tones in the given order, eg: `[N, S, F, R,
+/// X, J]` for the order of Telex keys.
+    pub fn with_tone_order(tone_order: [Tone; 6]) -> Collator {
+        Collator {
+            tone_order: tone_order,
+        }
+    }
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.sort_key(a).cmp(&self.sort_key(b))
+    }
+/// Return bytes which compare (as `[u8]`, eg: `memcmp`) like their strings
+/// compare with `compare`. Eg: for a database column.
+    pub fn sort_key(&self, s: &str) -> Vec<u8> {
+        let chars: Vec<(VChar, bool)> = s.chars().map(VChar::from_char).collect();
+        let mut ret = Vec::with_capacity(s.len() * 5 + 6);
+        // Letters, 3 bytes each
+        for (&(ref x, _), c) in chars.iter().zip(s.chars()) {
+            let weight = primary(x, c);
+            ret.extend_from_slice(&[(weight >> 16) as u8, (weight >> 8) as u8, weight as u8]);
+        }
+        // A separator lower than every weight, then tones.
+        ret.extend_from_slice(&[0, 0, 0]);
+        for &(ref x, _) in &chars {
+            let weight = match *x {
+                VChar::Vovel(_, _, ref tone) => {
+                    self.tone_order.iter().position(|x| x == tone).unwrap_or(6) + 1
+                },
+                _ => 1,
+            };
+            ret.push(weight as u8);
+        }
+        // Then case.
+        ret.push(0);
+        ret.extend(chars.iter().map(|&(_, upcase)| if upcase { 2 } else { 1 }));
+        ret
+    }
+}
+
+impl Default for Collator {
+    fn default() -> Collator { Collator::new() }
+}
+
+// Return the weight of `c` (read as `x`) without its tone and case.
+// ASCII characters other than letters weigh their code + 1, then come the
+// letters, then other characters.
+fn primary(x: &VChar, c: char) -> u32 {
+    let base = match *x {
+        VChar::Vovel(ref raw, ref flag, _) => {
+            VChar::Vovel(raw.clone(), flag.clone(), Tone::N).to_char()
+        },
+        VChar::Consonant(c) => c,
+        VChar::Invalid(c) => c,
+    };
+    match ALPHABET.iter().position(|x| *x == base) {
+        Some(i) => 0x80 + i as u32,
+        None if (c as u32) < 0x80 => c as u32 + 1,
+        None => c as u32 + 0x100,
+    }
+}
+
+/// Compare `a` and `b` with the default `Collator`.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    Collator::new().compare(a, b)
+}
+
+/// Return the sort key of `s` with the default `Collator`.
+pub fn sort_key(s: &str) -> Vec<u8> {
+    Collator::new().sort_key(s)
+}
+
+#[test]
+fn test_compare() {
+    let mut words = vec![
+        "đường", "Đà Nẵng", "xin", "ăn", "an", "ân", "ạ", "à", "á", "ả", "ã",
+        "a", "A", "ơ", "ô", "o", "ư", "u", "zebra", "yêu", "ca", "cá", "cà",
+        "da", "dă", "1a", "a b", "ab", "Ω", "",
+    ];
+    words.sort_by(|a, b| compare(a, b));
+    assert_eq!(words, vec![
+        "", "1a", "a", "A", "à", "ả", "ã", "á", "ạ", "a b", "ab", "an", "ăn",
+        "ân", "ca", "cà", "cá", "da", "dă", "Đà Nẵng", "đường", "o", "ô", "ơ",
+        "u", "ư", "xin", "yêu", "zebra", "Ω",
+    ]);
+
+    assert_eq!(compare("Việt", "Việt"), Ordering::Equal);
+    assert_eq!(compare("da", "đa"), Ordering::Less);
+    assert_eq!(compare("đa", "e"), Ordering::Less);
+    assert_eq!(compare("đb", "da"), Ordering::Greater);
+
+    let telex = Collator::with_tone_order([Tone::N, Tone::S, Tone::F, Tone::R, Tone::X, Tone::J]);
+    assert_eq!(telex.compare("cá", "cà"), Ordering::Less);
+    assert_eq!(compare("cá", "cà"), Ordering::Greater);
+}
+#[test]
+fn test_sort_key() {
+    let mut words = vec!["đ", "d", "e", "Đ", "dạ", "da", "dà", "Da", "d b", "db", "z", "ư"];
+    words.sort_by_key(|x| sort_key(x));
+    assert_eq!(words, vec!["d", "d b", "da", "Da", "dà", "dạ", "db", "đ", "Đ", "e", "ư", "z"]);
+    assert!(sort_key("dà") < sort_key("Dà"));
+    assert!(sort_key("Dà") < sort_key("đa"));
+}
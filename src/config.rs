@@ -0,0 +1,318 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+//! Load an `InputMethod` from a JSON file. Example:
+//!
+//! ```json
+//! {
+//!     "extends": "telex",
+//!     "marks": { "[": "uw", "]": "ow", "w": ["aw", "ow", "uw"] },
+//!     "tones": { "1": "s", "2": "f" },
+//!     "bindings": { "q": ["dd"] },
+//!     "options": { "auto_restore": true, "english_words": ["mix", "fax"] }
+//! }
+//! ```
+//!
+//! Every field is optional. Actions are named after the Telex keys (see
+//! `KeyType::from_name`), "tones" only accepts tones and "marks" only
+//! accepts the other actions. A key listed in the file replaces the
+//! bindings it has in the method it extends.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use rustc_serialize::json::{ self, Json };
+use input_method::{ InputMethod, KeyType };
+use util::Validation;
+use vword::TonePlacement;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Syntax(json::ParserError),
+    /// A field does not have the expected type. Eg: `"tones": 1`.
+    Type(String),
+    UnknownField(String),
+    /// `extends` or `InputMethod::load` names a missing input method.
+    UnknownMethod(String),
+    /// A key which is not exactly one character.
+    InvalidKey(String),
+    UnknownAction(char, String),
+    /// A known action in the wrong table, eg: `"tones": { "s": "aa" }`, and
+    /// what the table holds ("tone" or "mark").
+    WrongTable(char, String, &'static str),
+    UnknownOption(String),
+    /// A key is bound to two actions which can not be tried together,
+    /// eg: two tones.
+    Conflict(char, String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref e) => write!(f, "{}", e),
+            ConfigError::Syntax(ref e) => write!(f, "{}", e),
+            ConfigError::Type(ref s) => write!(f, "`{}` has a wrong type", s),
+            ConfigError::UnknownField(ref s) => write!(f, "unknown field `{}`", s),
+            ConfigError::UnknownMethod(ref s) => write!(f, "unknown input method `{}`", s),
+            ConfigError::InvalidKey(ref s) => write!(f, "`{}` is not a single key", s),
+            ConfigError::UnknownAction(c, ref s) => {
+                write!(f, "unknown action `{}` for key `{}`", s, c)
+            },
+            ConfigError::WrongTable(c, ref s, kind) => {
+                write!(f, "`{}` is not a {} (key `{}`)", s, kind, c)
+            },
+            ConfigError::UnknownOption(ref s) => write!(f, "unknown option `{}`", s),
+            ConfigError::Conflict(c, ref a, ref b) => {
+                write!(f, "key `{}` is bound to both `{}` and `{}`", c, a, b)
+            },
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> ConfigError { ConfigError::Io(e) }
+}
+
+impl From<json::ParserError> for ConfigError {
+    fn from(e: json::ParserError) -> ConfigError { ConfigError::Syntax(e) }
+}
+
+// Return `true` if `a` and `b` can be bound to the same key.
+// Only vovel toggles of different vovels can share a key, eg: 'w' in Telex.
+fn compatible(a: &KeyType, b: &KeyType) -> bool {
+    match (a, b) {
+        (&KeyType::Toggle(ref x, _), &KeyType::Toggle(ref y, _)) => x != y,
+        _ => false,
+    }
+}
+
+// Keys are case insensitive, see `InputMethod::bind`.
+fn parse_key(s: &str) -> Result<char, ConfigError> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c.to_lowercase().next().unwrap_or(c)),
+        _ => Err(ConfigError::InvalidKey(s.to_owned())),
+    }
+}
+
+// Return the names listed by a binding, which is a name or an array of names.
+fn parse_names(field: &str, value: &Json) -> Result<Vec<String>, ConfigError> {
+    match *value {
+        Json::String(ref s) => Ok(vec![s.clone()]),
+        Json::Array(ref arr) => arr.iter().map(|x| match *x {
+            Json::String(ref s) => Ok(s.clone()),
+            _ => Err(ConfigError::Type(field.to_owned())),
+        }).collect(),
+        _ => Err(ConfigError::Type(field.to_owned())),
+    }
+}
+
+fn parse_options(ret: &mut InputMethod, value: &Json) -> Result<(), ConfigError> {
+    let obj = match value.as_object() {
+        Some(x) => x,
+        None => return Err(ConfigError::Type("options".to_owned())),
+    };
+    for (name, value) in obj {
+        let options = ret.options_mut();
+        match name.as_str() {
+            "spell_check" => match value.as_boolean() {
+                Some(x) => options.spell_check = x,
+                None => return Err(ConfigError::Type(format!("options.{}", name))),
+            },
+            "insert_uw" => match value.as_boolean() {
+                Some(x) => options.insert_uw = x,
+                None => return Err(ConfigError::Type(format!("options.{}", name))),
+            },
+            "auto_restore" => match value.as_boolean() {
+                Some(x) => options.auto_restore = x,
+                None => return Err(ConfigError::Type(format!("options.{}", name))),
+            },
+            "tone_placement" => match value.as_string() {
+                Some("old") => options.tone_placement = Some(TonePlacement::Old),
+                Some("new") => options.tone_placement = Some(TonePlacement::New),
+                _ => return Err(ConfigError::Type(format!("options.{}", name))),
+            },
+            "validation" => match value.as_string() {
+                Some("lenient") => options.validation = Validation::Lenient,
+                Some("strict") => options.validation = Validation::Strict,
+                _ => return Err(ConfigError::Type(format!("options.{}", name))),
+            },
+            "english_words" => {
+                let field = format!("options.{}", name);
+                options.english_words = try!(parse_names(&field, value)).iter()
+                    .map(|x| x.to_lowercase()).collect();
+            },
+            _ => return Err(ConfigError::UnknownOption(name.clone())),
+        }
+    }
+    Ok(())
+}
+
+impl InputMethod {
+/// Return an input method described by a JSON string.
+    pub fn from_json(s: &str) -> Result<InputMethod, ConfigError> {
+        let root = try!(Json::from_str(s));
+        let root = match root.as_object() {
+            Some(x) => x,
+            None => return Err(ConfigError::Type("root".to_owned())),
+        };
+
+        let mut ret = match root.get("extends") {
+            None => InputMethod::empty(),
+            Some(&Json::String(ref name)) => match InputMethod::by_name(name) {
+                Some(x) => x,
+                None => return Err(ConfigError::UnknownMethod(name.clone())),
+            },
+            Some(_) => return Err(ConfigError::Type("extends".to_owned())),
+        };
+
+        // Collect the bindings of every key before checking them, as a key
+        // can be listed in more than one table.
+        let mut keys: BTreeMap<char, Vec<KeyType>> = BTreeMap::new();
+        for (field, value) in root {
+            let is_tone = match field.as_str() {
+                "tones" => Some(true),
+                "marks" => Some(false),
+                "bindings" => None,
+                "extends" => continue,
+                "options" => {
+                    try!(parse_options(&mut ret, value));
+                    continue;
+                },
+                _ => return Err(ConfigError::UnknownField(field.clone())),
+            };
+            let table = match value.as_object() {
+                Some(x) => x,
+                None => return Err(ConfigError::Type(field.clone())),
+            };
+            for (key, names) in table {
+                let c = try!(parse_key(key));
+                let actions = keys.entry(c).or_insert_with(Vec::new);
+                for name in try!(parse_names(&format!("{}.{}", field, key), names)) {
+                    let action = match KeyType::from_name(&name) {
+                        Some(x) => x,
+                        None => return Err(ConfigError::UnknownAction(c, name)),
+                    };
+                    let tone = if let KeyType::Tone(_) = action { true } else { false };
+                    if is_tone.map_or(false, |x| x != tone) {
+                        let kind = if tone { "mark" } else { "tone" };
+                        return Err(ConfigError::WrongTable(c, name, kind));
+                    }
+                    actions.push(action);
+                }
+            }
+        }
+
+        for (key, actions) in keys {
+            for (i, a) in actions.iter().enumerate() {
+                for b in actions.iter().skip(i + 1) {
+                    if !compatible(a, b) {
+                        return Err(ConfigError::Conflict(
+                            key, a.name().to_owned(), b.name().to_owned()
+                        ));
+                    }
+                }
+            }
+            ret.unbind(key);
+            for action in actions {
+                ret.bind(key, action);
+            }
+        }
+        Ok(ret)
+    }
+/// Return an input method described by a JSON file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<InputMethod, ConfigError> {
+        let mut s = String::new();
+        try!(try!(File::open(path)).read_to_string(&mut s));
+        InputMethod::from_json(&s)
+    }
+/// Return a built-in input method, or the one described in
+/// `<dir>/<name>.json`.
+    pub fn load<P: AsRef<Path>>(name: &str, dir: P) -> Result<InputMethod, ConfigError> {
+        if let Some(x) = InputMethod::by_name(name) {
+            return Ok(x);
+        }
+        let path = dir.as_ref().join(format!("{}.json", name));
+        if !path.is_file() {
+            return Err(ConfigError::UnknownMethod(name.to_owned()));
+        }
+        InputMethod::from_file(path)
+    }
+}
+
+#[test]
+fn test_from_json() {
+    use vword::{ Raw, Flag, Tone };
+
+    let x = InputMethod::from_json(r#"{
+        "extends": "telex",
+        "marks": { "[": "uw", "]": ["ow"], "W": ["aw", "ow", "uw"] },
+        "tones": { "1": "s" },
+        "options": { "spell_check": false }
+    }"#).unwrap();
+    assert_eq!(x.get_type('['), vec![KeyType::Toggle(Raw::U, Flag::W), KeyType::None]);
+    assert_eq!(x.get_type('1'), vec![KeyType::Tone(Tone::S), KeyType::None]);
+    assert_eq!(x.get_type('s'), vec![KeyType::Tone(Tone::S), KeyType::None]);
+    assert!(!x.options().spell_check);
+
+    let x = InputMethod::from_json(r#"{
+        "options": { "auto_restore": true, "english_words": ["Mix", "fax"] }
+    }"#).unwrap();
+    assert!(x.options().auto_restore);
+    assert_eq!(x.options().english_words.iter().collect::<Vec<_>>(), vec!["fax", "mix"]);
+    assert_eq!(x.options().tone_placement, None);
+    let x = InputMethod::from_json(r#"{ "options": { "tone_placement": "new" } }"#);
+    assert_eq!(x.unwrap().options().tone_placement, Some(TonePlacement::New));
+    let x = InputMethod::from_json(r#"{ "options": { "validation": "strict" } }"#);
+    assert_eq!(x.unwrap().options().validation, Validation::Strict);
+
+    // A key listed in the file replaces the one of the base
+    let x = InputMethod::from_json(r#"{ "extends": "telex", "bindings": { "w": "uw" } }"#);
+    assert_eq!(x.unwrap().get_type('w'),
+               vec![KeyType::Toggle(Raw::U, Flag::W), KeyType::None]);
+
+    assert_eq!(InputMethod::from_json("{}").unwrap(), InputMethod::empty());
+}
+#[test]
+fn test_from_json_error() {
+    fn test(s: &str, e: &str) {
+        match InputMethod::from_json(s) {
+            Ok(_) => panic!("{} should be rejected", s),
+            Err(x) => assert_eq!(x.to_string(), e),
+        }
+    }
+    test(r#"{ "tones": { "s": "aa" } }"#, "`aa` is not a tone (key `s`)");
+    test(r#"{ "marks": { "s": "s" } }"#, "`s` is not a mark (key `s`)");
+    test(r#"{ "tones": { "s": "xx" } }"#, "unknown action `xx` for key `s`");
+    test(r#"{ "bindings": { "s": "sắc" } }"#, "unknown action `sắc` for key `s`");
+    test(r#"{ "tones": { "s": "s" }, "marks": { "s": "aa" } }"#,
+         "key `s` is bound to both `aa` and `s`");
+    test(r#"{ "marks": { "a": ["aa", "aw"] } }"#, "key `a` is bound to both `aa` and `aw`");
+    test(r#"{ "tones": { "ss": "s" } }"#, "`ss` is not a single key");
+    test(r#"{ "options": { "fast": true } }"#, "unknown option `fast`");
+    test(r#"{ "options": { "spell_check": 1 } }"#, "`options.spell_check` has a wrong type");
+    test(r#"{ "extends": "dvorak" }"#, "unknown input method `dvorak`");
+    test(r#"{ "keys": {} }"#, "unknown field `keys`");
+    test(r#"{ "tones": [] }"#, "`tones` has a wrong type");
+}
+#[test]
+fn test_load() {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    let dir = env::temp_dir().join("vntyper_test_load");
+    fs::create_dir_all(&dir).unwrap();
+    File::create(dir.join("house.json")).unwrap()
+        .write_all(br#"{ "extends": "vni", "tones": { "s": "s" } }"#).unwrap();
+    let x = InputMethod::load("house", &dir).unwrap();
+    assert_eq!(x.get_type('s'), x.get_type('1'));
+    assert_eq!(InputMethod::load("telex", &dir).unwrap(), InputMethod::telex());
+    assert!(InputMethod::load("dvorak", &dir).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
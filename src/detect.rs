@@ -0,0 +1,191 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+//! Guess the encoding of Vietnamese text from its bytes: decode them as
+//! every candidate and score how Vietnamese the words read.
+
+use std::collections::HashSet;
+use encoding;
+use encoding::Encoding;
+use util;
+use vword::VWord;
+
+/// How the bytes of a text may be written.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Source {
+    Encoding(Encoding),
+    /// UTF-8 which was read as Windows-1252 and saved again as UTF-8, eg:
+    /// "Viá»‡t", see `encoding::fix_mojibake`.
+    Mojibake,
+}
+
+impl Source {
+/// Return the name of the source: the name of its encoding, or "mojibake".
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Source::Encoding(ref x) => x.name(),
+            Source::Mojibake => "mojibake",
+        }
+    }
+/// Return the text of `bytes` read from this source.
+    pub fn decode(&self, bytes: &[u8]) -> Option<String> {
+        match *self {
+            Source::Encoding(ref x) => x.decode(bytes).ok(),
+            Source::Mojibake => {
+                Encoding::Utf8.decode(bytes).ok().and_then(|x| encoding::fix_mojibake(&x))
+            },
+        }
+    }
+}
+
+/// A candidate source of the bytes and the text it reads.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Guess {
+    pub source: Source,
+    pub text: String,
+    /// The fraction of words which are Vietnamese, see `score`.
+    pub score: f64,
+    /// The score over the sum of the scores of every guess, from 0 to 1.
+    pub confidence: f64,
+}
+
+lazy_static! {
+    // The most frequent syllables of written Vietnamese.
+    static ref FREQUENT: HashSet<VWord> = vec![
+        "và", "của", "là", "có", "các", "không", "được", "người", "một",
+        "những", "trong", "cho", "với", "này", "đã", "đến", "ra", "để", "khi",
+        "thì", "năm", "cũng", "về", "như", "lại", "mà", "nhiều", "nhà", "đi",
+        "sẽ", "từ", "làm", "việc", "bị", "theo", "nhưng", "ở", "đó", "vào",
+        "ngày", "sự", "nước", "ông", "công", "hơn", "phải", "tôi", "anh",
+        "chúng", "nói", "thể", "đang", "rất", "tại", "nào", "họ", "sau", "trên",
+        "hai", "còn", "cùng", "mới", "biết", "đầu", "dân", "bà", "gì", "thành",
+        "động", "học", "quốc", "thời", "điều", "hội", "nhất", "việt", "nam",
+        "tế", "vì", "nếu", "hay", "muốn", "em", "chị", "trước", "ta", "đây",
+        "ai", "vẫn", "chỉ", "nhận", "hiện", "tin", "giờ", "phát", "bạn", "chào",
+        "xin", "cảm", "ơn", "vâng", "dạ", "nay", "hôm", "mai", "qua", "tới",
+        "đâu", "sao", "thế", "bao", "lúc", "con", "mẹ", "cha", "bố", "gia",
+        "đình", "ăn", "uống", "ngủ", "chơi", "xem", "nghe", "thấy", "đọc",
+        "viết", "yêu", "thương", "nhớ", "quên", "cười", "khóc", "vui", "buồn",
+        "đẹp", "xấu", "tốt", "lớn", "nhỏ", "nhanh", "chậm", "cũ", "nóng",
+        "lạnh", "trời", "mưa", "nắng", "gió", "đất", "sông", "núi", "biển",
+        "cây", "hoa", "lá", "đường", "phố", "chợ", "trường", "lớp", "bệnh",
+        "viện", "tiền", "mua", "bán", "giá", "rẻ", "đắt", "ít", "hết", "cả",
+        "mọi", "mỗi", "vài", "đều", "luôn", "thường", "rồi", "chưa", "sắp",
+        "vừa", "đúng", "sai", "thật", "quá", "lắm", "hơi", "khá", "cần", "nên",
+        "giúp", "đỡ", "hỏi", "trả", "lời", "gọi", "chờ", "đợi", "đưa", "mang",
+        "lấy", "ý", "nghĩ", "hiểu", "tìm", "thử", "chúc", "mừng", "khỏe",
+        "mạnh", "sức", "vậy", "kia", "ấy", "đấy", "nhé", "nhỉ", "à", "ạ", "ơi",
+        "kìa", "bên", "ngoài", "giữa", "dưới", "cao", "thấp", "xa", "gần",
+        "sáng", "tối", "chiều", "trưa", "đêm", "tuần", "tháng", "giây", "phút",
+        "lần", "cuối", "thứ", "số", "ba", "bốn", "sáu", "bảy", "tám", "chín",
+        "mười", "trăm", "nghìn", "triệu", "tỷ",
+    ].into_iter().map(VWord::from_str).collect();
+}
+
+// Return `true` if `word` is a Vietnamese syllable. Misread bytes often
+// put a capital inside a word ("bÕn") or a second tone on it ("bỏẳăn"),
+// which `util::is_vietnamese` alone lets through.
+fn is_syllable(word: &VWord) -> bool {
+    let capital_inside = word.iter().zip(word.iter().skip(1)).any(|((_, a), (_, b))| !*a && *b);
+    !capital_inside && util::is_vietnamese(word) && util::validate(word).is_ok()
+}
+
+/// Return how Vietnamese `text` reads, from 0 to 1: the mean of the fraction
+/// of words which are syllables (accepted by `util::is_vietnamese` and
+/// `util::validate`, without a capital after a lowercase letter) and the
+/// fraction of words which are among the most frequent syllables.
+/// Return 0 for a text without words.
+pub fn score(text: &str) -> f64 {
+    let words: Vec<VWord> = text.split_whitespace()
+        .map(|x| x.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|x| x.chars().any(char::is_alphabetic))
+        .map(VWord::from_str)
+        .collect();
+    if words.is_empty() {
+        return 0.0;
+    }
+    let valid = words.iter().filter(|x| is_syllable(x)).count();
+    let frequent = words.iter().filter(|x| FREQUENT.contains(&x.to_lowercase())).count();
+    (valid + frequent) as f64 / (2 * words.len()) as f64
+}
+
+/// Decode `bytes` as UTF-8, mojibake UTF-8, VNI-Windows, TCVN3, VISCII and
+/// Windows-1258, and return the sources which decode them, from the most
+/// likely. Sources which read the same text (eg: for ASCII) keep that order.
+pub fn detect(bytes: &[u8]) -> Vec<Guess> {
+    let sources = vec![
+        Source::Encoding(Encoding::Utf8),
+        Source::Mojibake,
+        Source::Encoding(Encoding::Vni),
+        Source::Encoding(Encoding::Tcvn3),
+        Source::Encoding(Encoding::Viscii),
+        Source::Encoding(Encoding::Windows1258),
+    ];
+    let mut ret: Vec<Guess> = sources.into_iter()
+        .filter_map(|source| source.decode(bytes).map(|text| {
+            let score = score(&text);
+            Guess {
+                source: source,
+                text: text,
+                score: score,
+                confidence: 0.0,
+            }
+        }))
+        .collect();
+    let total: f64 = ret.iter().map(|x| x.score).sum();
+    for x in &mut ret {
+        x.confidence = if total > 0.0 { x.score / total } else { 0.0 };
+    }
+    // A stable sort keeps the order of the sources for equal scores.
+    ret.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    ret
+}
+
+#[test]
+fn test_detect() {
+    let text = "Tiếng Việt là ngôn ngữ của người Việt và là ngôn ngữ chính thức \
+                tại Việt Nam. Đà Nẵng, ĐƯỜNG PHỐ.";
+    for encoding in Encoding::all() {
+        let guesses = detect(&encoding.encode(text).unwrap());
+        let source = Source::Encoding(encoding);
+        assert_eq!((&source, &guesses[0].source), (&source, &source));
+        assert_eq!(guesses[0].text, text);
+        assert!(guesses[0].confidence > guesses[1].confidence);
+        let total: f64 = guesses.iter().map(|x| x.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    // Short phrases read right in every encoding, though encodings which
+    // share their bytes may tie.
+    for text in &["Xin chào các bạn", "cảm ơn", "Hôm nay trời đẹp quá",
+                  "Cảm ơn anh nhiều", "Đi đâu vậy?", "Chúc mừng năm mới",
+                  "TRƯỜNG HỌC", "Bạn khỏe không"] {
+        for encoding in Encoding::all() {
+            let guesses = detect(&encoding.encode(text).unwrap());
+            assert_eq!((text, &encoding, guesses[0].text.as_str()), (text, &encoding, *text));
+        }
+    }
+
+    let guesses = detect("Viá»‡t Nam lÃ\u{A0} quÃª hÆ°Æ¡ng cá»§a tÃ´i".as_bytes());
+    assert_eq!(guesses[0].source, Source::Mojibake);
+    assert_eq!(guesses[0].text, "Việt Nam là quê hương của tôi");
+
+    // ASCII reads the same in every encoding.
+    let guesses = detect(b"Xin chao");
+    assert_eq!(guesses[0].source, Source::Encoding(Encoding::Utf8));
+    assert_eq!(guesses[0].confidence, guesses[1].confidence);
+    assert!(detect(b"").iter().all(|x| x.confidence == 0.0));
+}
+#[test]
+fn test_score() {
+    assert_eq!(score("của người"), 1.0);
+    assert_eq!(score("Việt Nam!"), 1.0);
+    assert_eq!(score("xin chào"), 1.0);
+    assert_eq!(score("hoàng hôn"), 0.5);
+    assert_eq!(score("Vieät Nam"), 0.5);
+    assert_eq!(score("42 !"), 0.0);
+    assert_eq!(score("HOÀNG HÔN"), 0.5);
+    assert_eq!(score("bÕn"), 0.0);
+    assert_eq!(score("bỏẳăn"), 0.0);
+}
@@ -0,0 +1,93 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+/// The unit counted by backspaces.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Unit {
+    /// One Unicode scalar value (a `char`).
+    Scalar,
+    /// A base character and the combining marks which follow it, eg:
+    /// "e\u{302}\u{301}" (ế in NFD) is one unit.
+    Grapheme,
+}
+
+/// An edit for front-ends which type into the application: delete
+/// `backspaces` units before the cursor, then type `insert`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Edit {
+    pub backspaces: usize,
+    pub insert: String,
+}
+
+// Return `true` for the combining diacritical marks.
+fn is_combining(c: char) -> bool {
+    [(0x0300, 0x036F), (0x1AB0, 0x1AFF), (0x1DC0, 0x1DFF), (0x20D0, 0x20FF), (0xFE20, 0xFE2F)]
+        .iter().any(|&(x, y)| x <= c as u32 && c as u32 <= y)
+}
+
+// Split `s` into units.
+fn units<'a>(s: &'a str, unit: &Unit) -> Vec<&'a str> {
+    let mut ret: Vec<&str> = Vec::new();
+    let mut start = 0;
+    for (i, c) in s.char_indices().skip(1) {
+        if *unit == Unit::Scalar || !is_combining(c) {
+            ret.push(&s[start..i]);
+            start = i;
+        }
+    }
+    if start < s.len() {
+        ret.push(&s[start..]);
+    }
+    ret
+}
+
+impl Edit {
+/// Return the edit which turns `old` into `new`. Only the units after
+/// the common prefix are deleted.
+    pub fn new(old: &str, new: &str, unit: &Unit) -> Edit {
+        let old = units(old, unit);
+        let new = units(new, unit);
+        let prefix = old.iter().zip(new.iter()).take_while(|&(x, y)| x == y).count();
+        Edit {
+            backspaces: old.len() - prefix,
+            insert: new[prefix..].concat(),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.backspaces == 0 && self.insert.is_empty()
+    }
+/// Apply the edit to the end of `text`.
+    pub fn apply(&self, text: &mut String, unit: &Unit) {
+        let len = {
+            let x = units(text, unit);
+            let n = x.len().saturating_sub(self.backspaces);
+            x[..n].iter().map(|x| x.len()).sum()
+        };
+        text.truncate(len);
+        text.push_str(&self.insert);
+    }
+}
+
+#[test]
+fn test_edit() {
+    fn test(old: &str, new: &str, unit: Unit, backspaces: usize, insert: &str) {
+        let edit = Edit::new(old, new, &unit);
+        assert_eq!((old, new, &edit), (old, new, &Edit {
+            backspaces: backspaces, insert: insert.to_owned()
+        }));
+        let mut text = "x ".to_owned() + old;
+        edit.apply(&mut text, &unit);
+        assert_eq!(text, "x ".to_owned() + new);
+    }
+    test("cha", "chao", Unit::Scalar, 0, "o");
+    test("chao", "chào", Unit::Scalar, 2, "ào");
+    test("chaus", "chau", Unit::Scalar, 1, "");
+    test("", "", Unit::Scalar, 0, "");
+    test("Việt", "Viêt.", Unit::Grapheme, 2, "êt.");
+
+    // Combining marks
+    test("che\u{302}", "che\u{302}\u{301}", Unit::Scalar, 0, "\u{301}");
+    test("che\u{302}", "che\u{302}\u{301}", Unit::Grapheme, 1, "e\u{302}\u{301}");
+    test("ti\u{301}e\u{302}", "tie\u{302}\u{301}", Unit::Grapheme, 2, "ie\u{302}\u{301}");
+}
@@ -0,0 +1,441 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+//! Convert text between Unicode and the encodings of the fonts used before
+//! it, eg: "Việt" is "Vi\xD6t" in TCVN3 and "Vie\xE4t" ("Vieät") in
+//! VNI-Windows.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use vword::{ VWord, VChar, Raw, Flag, Tone, Normalization };
+
+/// An encoding of Vietnamese text.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+pub enum Encoding {
+    Utf8,
+    /// TCVN 5712:1993. The ABC fonts (TCVN3) use its bytes from 0xA0, it
+    /// adds the uppercase letters with a tone in 0x80..0x9F and in some
+    /// control codes.
+    Tcvn3,
+    /// VNI-Windows: a Latin letter followed by a byte for its marks, eg: "ấ"
+    /// is "a" 0xE1. 'đ', 'ơ', 'ư' and 'i' with a tone have their own byte.
+    Vni,
+    Viscii,
+    /// A vovel without a byte of its own is written with its tone as a
+    /// combining mark, eg: "ấ" is 0xE2 0xEC.
+    Windows1258,
+}
+
+/// The error of `Encoding::encode` or `Encoding::decode`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EncodingError {
+    /// A character which the encoding cannot write, and its position. A
+    /// vovel and its combining marks count as one character.
+    Unmappable(usize, char),
+    /// A byte which means nothing in the encoding, and its offset.
+    Undefined(usize, u8),
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EncodingError::Unmappable(i, c) => write!(f, "cannot encode `{}` at {}", c, i),
+            EncodingError::Undefined(i, b) => write!(f, "undefined byte 0x{:02X} at {}", b, i),
+        }
+    }
+}
+
+// A single byte encoding: the letters it puts in place of control codes,
+// and the characters of the bytes from 0x80. U+FFFD marks an undefined byte.
+struct Table {
+    c0: &'static [(u8, char)],
+    high: &'static [char; 128],
+}
+
+impl Table {
+    fn get(&self, b: u8) -> Option<char> {
+        if b < 0x80 {
+            Some(self.c0.iter().find(|x| x.0 == b).map_or(b as char, |x| x.1))
+        } else {
+            match self.high[b as usize - 0x80] {
+                '\u{FFFD}' => None,
+                c => Some(c),
+            }
+        }
+    }
+    fn reverse(&self) -> BTreeMap<char, u8> {
+        (0..256).filter_map(|b| self.get(b as u8).map(|c| (c, b as u8))).collect()
+    }
+}
+
+impl Encoding {
+/// Return an encoding from its name: "utf-8", "tcvn3" (or "abc"), "vni",
+/// "viscii", "windows-1258" (or "cp1258"). Case is ignored.
+    pub fn from_name(name: &str) -> Option<Encoding> {
+        Some(match name.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Encoding::Utf8,
+            "tcvn3" | "abc" => Encoding::Tcvn3,
+            "vni" | "vni-windows" => Encoding::Vni,
+            "viscii" => Encoding::Viscii,
+            "windows-1258" | "cp1258" => Encoding::Windows1258,
+            _ => return None,
+        })
+    }
+/// Return the name of the encoding, see `Encoding::from_name`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Tcvn3 => "tcvn3",
+            Encoding::Vni => "vni",
+            Encoding::Viscii => "viscii",
+            Encoding::Windows1258 => "windows-1258",
+        }
+    }
+/// Return every encoding.
+    pub fn all() -> Vec<Encoding> {
+        vec![Encoding::Utf8, Encoding::Tcvn3, Encoding::Vni, Encoding::Viscii,
+             Encoding::Windows1258]
+    }
+
+/// Return `text` in the encoding. Decomposed text (NFD) is read like
+/// precomposed text.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, EncodingError> {
+        match *self {
+            Encoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            Encoding::Vni => encode_vni(text),
+            Encoding::Tcvn3 => encode_table(text, &TCVN3_REVERSE),
+            Encoding::Viscii => encode_table(text, &VISCII_REVERSE),
+            Encoding::Windows1258 => encode_table(text, &WINDOWS_1258_REVERSE),
+        }
+    }
+/// Return the text of `bytes` in the encoding, with precomposed vovels.
+    pub fn decode(&self, bytes: &[u8]) -> Result<String, EncodingError> {
+        match *self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).map_err(|e| {
+                let i = e.utf8_error().valid_up_to();
+                EncodingError::Undefined(i, bytes[i])
+            }),
+            Encoding::Vni => decode_vni(bytes),
+            Encoding::Tcvn3 => decode_table(bytes, &TCVN3),
+            Encoding::Viscii => decode_table(bytes, &VISCII),
+            Encoding::Windows1258 => decode_table(bytes, &WINDOWS_1258),
+        }
+    }
+}
+
+lazy_static! {
+    static ref TCVN3_REVERSE: BTreeMap<char, u8> = TCVN3.reverse();
+    static ref VISCII_REVERSE: BTreeMap<char, u8> = VISCII.reverse();
+    static ref WINDOWS_1258_REVERSE: BTreeMap<char, u8> = WINDOWS_1258.reverse();
+    static ref WINDOWS_1252_REVERSE: BTreeMap<char, u8> = WINDOWS_1252.reverse();
+}
+
+// Return `text` in a single byte encoding, from the bytes of its characters.
+fn encode_table(text: &str, reverse: &BTreeMap<char, u8>) -> Result<Vec<u8>, EncodingError> {
+    let encode = |s: String| -> Option<Vec<u8>> {
+        s.chars().map(|c| reverse.get(&c).cloned()).collect()
+    };
+    let mut ret = Vec::with_capacity(text.len());
+    for (i, (x, up)) in VWord::from_str(text).iter().enumerate() {
+        let c = VWord::new_raw(vec![x.clone()], vec![*up]);
+        // Without a byte of its own, the tone may be a combining mark.
+        match encode(c.to_string())
+            .or_else(|| encode(c.render(&Normalization::Windows1258))) {
+            Some(x) => ret.extend(x),
+            None => {
+                let c = c.to_string().chars().next().unwrap_or(' ');
+                return Err(EncodingError::Unmappable(i, c));
+            },
+        }
+    }
+    Ok(ret)
+}
+
+// Return the text of `bytes` in a single byte encoding.
+fn decode_table(bytes: &[u8], table: &Table) -> Result<String, EncodingError> {
+    let mut ret = String::with_capacity(bytes.len());
+    for (i, &b) in bytes.iter().enumerate() {
+        match table.get(b) {
+            Some(c) => ret.push(c),
+            None => return Err(EncodingError::Undefined(i, b)),
+        }
+    }
+    // Join the combining tones to their vovels.
+    Ok(VWord::from_str(&ret).to_string())
+}
+
+/// Return the text of UTF-8 bytes which were read as Windows-1252 and saved
+/// again as UTF-8, eg: "Viá»‡t" is "Việt". Return `None` if `text` cannot be
+/// such a mistake.
+pub fn fix_mojibake(text: &str) -> Option<String> {
+    // The bytes which Windows-1252 leaves undefined are often read as the
+    // control codes of the same value.
+    let bytes: Option<Vec<u8>> = text.chars()
+        .map(|c| match WINDOWS_1252_REVERSE.get(&c) {
+            Some(&b) => Some(b),
+            None if (c as u32) < 0x100 => Some(c as u8),
+            None => None,
+        })
+        .collect();
+    bytes.and_then(|x| String::from_utf8(x).ok())
+}
+
+// The letters which have a byte of their own in VNI, in lowercase.
+const VNI_LETTERS: [(u8, VChar); 9] = [
+    (0xF1, VChar::Consonant('đ')),
+    (0xF4, VChar::Vovel(Raw::O, Flag::W, Tone::N)),
+    (0xF6, VChar::Vovel(Raw::U, Flag::W, Tone::N)),
+    (0xED, VChar::Vovel(Raw::I, Flag::N, Tone::S)),
+    (0xEC, VChar::Vovel(Raw::I, Flag::N, Tone::F)),
+    (0xE6, VChar::Vovel(Raw::I, Flag::N, Tone::R)),
+    (0xF3, VChar::Vovel(Raw::I, Flag::N, Tone::X)),
+    (0xF2, VChar::Vovel(Raw::I, Flag::N, Tone::J)),
+    (0xEE, VChar::Vovel(Raw::Y, Flag::N, Tone::J)),
+];
+// The bytes of the marks which follow a vovel in VNI, in lowercase:
+// `Flag::N` is a tone alone, `Flag::D` the circumflex of 'a', 'e', 'o'
+// and `Flag::W` the breve of 'a', with a tone.
+const VNI_MARKS: [(u8, Flag, Tone); 17] = [
+    (0xF9, Flag::N, Tone::S), (0xF8, Flag::N, Tone::F), (0xFB, Flag::N, Tone::R),
+    (0xF5, Flag::N, Tone::X), (0xEF, Flag::N, Tone::J),
+    (0xE2, Flag::D, Tone::N), (0xE1, Flag::D, Tone::S), (0xE0, Flag::D, Tone::F),
+    (0xE5, Flag::D, Tone::R), (0xE3, Flag::D, Tone::X), (0xE4, Flag::D, Tone::J),
+    (0xEA, Flag::W, Tone::N), (0xE9, Flag::W, Tone::S), (0xE8, Flag::W, Tone::F),
+    (0xFA, Flag::W, Tone::R), (0xFC, Flag::W, Tone::X), (0xEB, Flag::W, Tone::J),
+];
+
+// Return the lowercase of a VNI byte, and whether it was uppercase.
+fn vni_lower(b: u8) -> (u8, bool) {
+    if b >= 0xC0 && b < 0xE0 { (b + 0x20, true) } else { (b, false) }
+}
+fn vni_upper(b: u8) -> u8 {
+    if (b >= b'a' && b <= b'z') || b >= 0xE0 { b - 0x20 } else { b }
+}
+// Return `true` if VNI reads `b` as a letter or a mark.
+fn is_vni_letter(b: u8) -> bool {
+    let b = vni_lower(b).0;
+    VNI_LETTERS.iter().any(|x| x.0 == b) || VNI_MARKS.iter().any(|x| x.0 == b)
+}
+// Return the flag and tone of a vovel followed by the byte `b`, if it is a
+// mark which the vovel can take.
+fn vni_mark(raw: &Raw, flag: &Flag, tone: &Tone, b: u8) -> Option<(Flag, Tone)> {
+    let b = vni_lower(b).0;
+    match VNI_MARKS.iter().find(|x| x.0 == b) {
+        Some(&(_, ref f, ref t)) if *tone == Tone::N => match (raw, flag, f) {
+            (_, _, &Flag::N) => Some((flag.clone(), t.clone())),
+            (&Raw::A, &Flag::N, _)
+            | (&Raw::E, &Flag::N, &Flag::D) | (&Raw::O, &Flag::N, &Flag::D) => {
+                Some((f.clone(), t.clone()))
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn encode_vni(text: &str) -> Result<Vec<u8>, EncodingError> {
+    let mut ret = Vec::with_capacity(text.len());
+    for (i, (x, &up)) in VWord::from_str(text).iter().enumerate() {
+        let letter = |x: &VChar| VNI_LETTERS.iter().find(|y| y.1 == *x).map(|y| y.0);
+        let c = VWord::new_raw(vec![x.clone()], vec![up]).to_string().chars().next().unwrap_or(' ');
+        let mut bytes = Vec::new();
+        match *x {
+            VChar::Vovel(ref raw, ref flag, ref tone) => {
+                if let Some(b) = letter(x) {
+                    bytes.push(b);
+                } else {
+                    // 'ơ', 'ư' and the Latin letters take the marks left.
+                    let base = VChar::Vovel(raw.clone(), Flag::N, Tone::N);
+                    let (b, flag) = match letter(&VChar::Vovel(raw.clone(), flag.clone(), Tone::N)) {
+                        Some(b) => (b, Flag::N),
+                        None => (base.to_char() as u8, flag.clone()),
+                    };
+                    bytes.push(b);
+                    if flag != Flag::N || *tone != Tone::N {
+                        match VNI_MARKS.iter().find(|y| y.1 == flag && y.2 == *tone) {
+                            Some(y) => bytes.push(y.0),
+                            None => return Err(EncodingError::Unmappable(i, c)),
+                        }
+                    }
+                }
+            },
+            VChar::Consonant('đ') => bytes.push(0xF1),
+            _ => {
+                // A byte which VNI reads as a letter or a mark cannot be
+                // written as is.
+                match WINDOWS_1252_REVERSE.get(&c) {
+                    Some(&b) if c.is_ascii() || !is_vni_letter(b) => ret.push(b),
+                    _ => return Err(EncodingError::Unmappable(i, c)),
+                }
+                continue;
+            },
+        }
+        ret.extend(bytes.into_iter().map(|b| if up { vni_upper(b) } else { b }));
+    }
+    Ok(ret)
+}
+
+fn decode_vni(bytes: &[u8]) -> Result<String, EncodingError> {
+    let mut ret = VWord::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let (b, up) = vni_lower(bytes[i]);
+        let (mut x, up) = match VNI_LETTERS.iter().find(|y| y.0 == b) {
+            Some(y) => (y.1.clone(), up),
+            None => match WINDOWS_1252.get(bytes[i]) {
+                Some(c) => VChar::from_char(c),
+                None => return Err(EncodingError::Undefined(i, bytes[i])),
+            },
+        };
+        i += 1;
+        if let VChar::Vovel(ref raw, ref mut flag, ref mut tone) = x {
+            if let Some((f, t)) = bytes.get(i).and_then(|b| vni_mark(raw, flag, tone, *b)) {
+                *flag = f;
+                *tone = t;
+                i += 1;
+            }
+        }
+        ret.push(x, up);
+    }
+    Ok(ret.to_string())
+}
+
+static TCVN3: Table = Table {
+    c0: &[
+        (0x01, 'Ú'), (0x02, 'Ụ'), (0x04, 'Ừ'), (0x05, 'Ử'), (0x06, 'Ữ'), (0x11, 'Ứ'),
+        (0x12, 'Ự'), (0x13, 'Ỳ'), (0x14, 'Ỷ'), (0x15, 'Ỹ'), (0x16, 'Ý'), (0x17, 'Ỵ'),
+    ],
+    high: &[
+        'À', 'Ả', 'Ã', 'Á', 'Ạ', 'Ặ', 'Ậ', 'È',
+        'Ẻ', 'Ẽ', 'É', 'Ẹ', 'Ệ', 'Ì', 'Ỉ', 'Ĩ',
+        'Í', 'Ị', 'Ò', 'Ỏ', 'Õ', 'Ó', 'Ọ', 'Ộ',
+        'Ờ', 'Ở', 'Ỡ', 'Ớ', 'Ợ', 'Ù', 'Ủ', 'Ũ',
+        '\u{A0}', 'Ă', 'Â', 'Ê', 'Ô', 'Ơ', 'Ư', 'Đ',
+        'ă', 'â', 'ê', 'ô', 'ơ', 'ư', 'đ', 'Ằ',
+        '\u{300}', '\u{309}', '\u{303}', '\u{301}', '\u{323}', 'à', 'ả', 'ã',
+        'á', 'ạ', 'Ẳ', 'ằ', 'ẳ', 'ẵ', 'ắ', 'Ẵ',
+        'Ắ', 'Ầ', 'Ẩ', 'Ẫ', 'Ấ', 'Ề', 'ặ', 'ầ',
+        'ẩ', 'ẫ', 'ấ', 'ậ', 'è', 'Ể', 'ẻ', 'ẽ',
+        'é', 'ẹ', 'ề', 'ể', 'ễ', 'ế', 'ệ', 'ì',
+        'ỉ', 'Ễ', 'Ế', 'Ồ', 'ĩ', 'í', 'ị', 'ò',
+        'Ổ', 'ỏ', 'õ', 'ó', 'ọ', 'ồ', 'ổ', 'ỗ',
+        'ố', 'ộ', 'ờ', 'ở', 'ỡ', 'ớ', 'ợ', 'ù',
+        'Ỗ', 'ủ', 'ũ', 'ú', 'ụ', 'ừ', 'ử', 'ữ',
+        'ứ', 'ự', 'ỳ', 'ỷ', 'ỹ', 'ý', 'ỵ', 'Ố',
+    ],
+};
+static VISCII: Table = Table {
+    c0: &[
+        (0x02, 'Ẳ'), (0x05, 'Ẵ'), (0x06, 'Ẫ'), (0x14, 'Ỷ'), (0x19, 'Ỹ'), (0x1E, 'Ỵ'),
+    ],
+    high: &[
+        'Ạ', 'Ắ', 'Ằ', 'Ặ', 'Ấ', 'Ầ', 'Ẩ', 'Ậ',
+        'Ẽ', 'Ẹ', 'Ế', 'Ề', 'Ể', 'Ễ', 'Ệ', 'Ố',
+        'Ồ', 'Ổ', 'Ỗ', 'Ộ', 'Ợ', 'Ớ', 'Ờ', 'Ở',
+        'Ị', 'Ỏ', 'Ọ', 'Ỉ', 'Ủ', 'Ũ', 'Ụ', 'Ỳ',
+        'Õ', 'ắ', 'ằ', 'ặ', 'ấ', 'ầ', 'ẩ', 'ậ',
+        'ẽ', 'ẹ', 'ế', 'ề', 'ể', 'ễ', 'ệ', 'ố',
+        'ồ', 'ổ', 'ỗ', 'Ỡ', 'Ơ', 'ộ', 'ờ', 'ở',
+        'ị', 'Ự', 'Ứ', 'Ừ', 'Ử', 'ơ', 'ớ', 'Ư',
+        'À', 'Á', 'Â', 'Ã', 'Ả', 'Ă', 'ẳ', 'ẵ',
+        'È', 'É', 'Ê', 'Ẻ', 'Ì', 'Í', 'Ĩ', 'ỳ',
+        'Đ', 'ứ', 'Ò', 'Ó', 'Ô', 'ạ', 'ỷ', 'ừ',
+        'ử', 'Ù', 'Ú', 'ỹ', 'ỵ', 'Ý', 'ỡ', 'ư',
+        'à', 'á', 'â', 'ã', 'ả', 'ă', 'ữ', 'ẫ',
+        'è', 'é', 'ê', 'ẻ', 'ì', 'í', 'ĩ', 'ỉ',
+        'đ', 'ự', 'ò', 'ó', 'ô', 'õ', 'ỏ', 'ọ',
+        'ụ', 'ù', 'ú', 'ũ', 'ủ', 'ý', 'ợ', 'Ữ',
+    ],
+};
+static WINDOWS_1258: Table = Table {
+    c0: &[],
+    high: &[
+        '€', '\u{FFFD}', '‚', 'ƒ', '„', '…', '†', '‡',
+        'ˆ', '‰', '\u{FFFD}', '‹', 'Œ', '\u{FFFD}', '\u{FFFD}', '\u{FFFD}',
+        '\u{FFFD}', '‘', '’', '“', '”', '•', '–', '—',
+        '˜', '™', '\u{FFFD}', '›', 'œ', '\u{FFFD}', '\u{FFFD}', 'Ÿ',
+        '\u{A0}', '¡', '¢', '£', '¤', '¥', '¦', '§',
+        '¨', '©', 'ª', '«', '¬', '\u{AD}', '®', '¯',
+        '°', '±', '²', '³', '´', 'µ', '¶', '·',
+        '¸', '¹', 'º', '»', '¼', '½', '¾', '¿',
+        'À', 'Á', 'Â', 'Ă', 'Ä', 'Å', 'Æ', 'Ç',
+        'È', 'É', 'Ê', 'Ë', '\u{300}', 'Í', 'Î', 'Ï',
+        'Đ', 'Ñ', '\u{309}', 'Ó', 'Ô', 'Ơ', 'Ö', '×',
+        'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'Ư', '\u{303}', 'ß',
+        'à', 'á', 'â', 'ă', 'ä', 'å', 'æ', 'ç',
+        'è', 'é', 'ê', 'ë', '\u{301}', 'í', 'î', 'ï',
+        'đ', 'ñ', '\u{323}', 'ó', 'ô', 'ơ', 'ö', '÷',
+        'ø', 'ù', 'ú', 'û', 'ü', 'ư', '₫', 'ÿ',
+    ],
+};
+// The bytes of VNI which are not letters.
+static WINDOWS_1252: Table = Table {
+    c0: &[],
+    high: &[
+        '€', '\u{FFFD}', '‚', 'ƒ', '„', '…', '†', '‡',
+        'ˆ', '‰', 'Š', '‹', 'Œ', '\u{FFFD}', 'Ž', '\u{FFFD}',
+        '\u{FFFD}', '‘', '’', '“', '”', '•', '–', '—',
+        '˜', '™', 'š', '›', 'œ', '\u{FFFD}', 'ž', 'Ÿ',
+        '\u{A0}', '¡', '¢', '£', '¤', '¥', '¦', '§',
+        '¨', '©', 'ª', '«', '¬', '\u{AD}', '®', '¯',
+        '°', '±', '²', '³', '´', 'µ', '¶', '·',
+        '¸', '¹', 'º', '»', '¼', '½', '¾', '¿',
+        'À', 'Á', 'Â', 'Ã', 'Ä', 'Å', 'Æ', 'Ç',
+        'È', 'É', 'Ê', 'Ë', 'Ì', 'Í', 'Î', 'Ï',
+        'Ð', 'Ñ', 'Ò', 'Ó', 'Ô', 'Õ', 'Ö', '×',
+        'Ø', 'Ù', 'Ú', 'Û', 'Ü', 'Ý', 'Þ', 'ß',
+        'à', 'á', 'â', 'ã', 'ä', 'å', 'æ', 'ç',
+        'è', 'é', 'ê', 'ë', 'ì', 'í', 'î', 'ï',
+        'ð', 'ñ', 'ò', 'ó', 'ô', 'õ', 'ö', '÷',
+        'ø', 'ù', 'ú', 'û', 'ü', 'ý', 'þ', 'ÿ',
+    ],
+};
+
+#[test]
+fn test_encode() {
+    let text = "Việt Nam, Đà Nẵng, ĐƯỜNG";
+    assert_eq!(Encoding::Tcvn3.encode(text).unwrap(),
+               b"Vi\xD6t Nam, \xA7\xB5 N\xBDng, \xA7\xA6\x98NG".to_vec());
+    assert_eq!(Encoding::Vni.encode(text).unwrap(),
+               b"Vie\xE4t Nam, \xD1a\xF8 Na\xFCng, \xD1\xD6\xD4\xD8NG".to_vec());
+    assert_eq!(Encoding::Viscii.encode(text).unwrap(),
+               b"Vi\xAEt Nam, \xD0\xE0 N\xC7ng, \xD0\xBF\x96NG".to_vec());
+    assert_eq!(Encoding::Windows1258.encode(text).unwrap(),
+               b"Vi\xEA\xF2t Nam, \xD0\xE0 N\xE3\xDEng, \xD0\xDD\xD5\xCCNG".to_vec());
+    assert_eq!(Encoding::Utf8.encode(text).unwrap(), text.as_bytes().to_vec());
+    // Decomposed text, 'i' with a tone and 'y' with nặng in VNI.
+    assert_eq!(Encoding::Vni.encode("Vie\u{302}\u{323}t, í, Ỵ").unwrap(),
+               b"Vie\xE4t, \xED, \xCE".to_vec());
+
+    assert_eq!(Encoding::Tcvn3.encode("xin chào Ω"), Err(EncodingError::Unmappable(9, 'Ω')));
+    assert_eq!(Encoding::Viscii.encode("\u{2}"), Err(EncodingError::Unmappable(0, '\u{2}')));
+    // 'ñ' is read as 'đ' in VNI.
+    assert_eq!(Encoding::Vni.encode("mañana"), Err(EncodingError::Unmappable(2, 'ñ')));
+    assert_eq!(Encoding::Vni.encode("“ok” ©").unwrap(), b"\x93ok\x94 \xA9".to_vec());
+}
+#[test]
+fn test_decode() {
+    let text = "Việt Nam, Đà Nẵng, ĐƯỜNG";
+    for encoding in &Encoding::all() {
+        let bytes = encoding.encode(text).unwrap();
+        assert_eq!((encoding, encoding.decode(&bytes).unwrap()), (encoding, text.to_owned()));
+    }
+    // The combining marks of TCVN3.
+    assert_eq!(Encoding::Tcvn3.decode(b"Vi\xAA\xB4t").unwrap(), "Việt");
+    assert_eq!(Encoding::Vni.decode(b"VIE\xC4T, vie\xC4t, i\xF9").unwrap(), "VIỆT, việt, í");
+    assert_eq!(Encoding::Vni.decode(b"\xD1\xD6\xD4\xF8NG").unwrap(), "ĐƯỜNG");
+    assert_eq!(Encoding::Windows1258.decode(b"a\x81"), Err(EncodingError::Undefined(1, 0x81)));
+    assert_eq!(Encoding::Utf8.decode(b"a\xFF"), Err(EncodingError::Undefined(1, 0xFF)));
+
+    for encoding in &Encoding::all() {
+        assert_eq!(Encoding::from_name(encoding.name()).as_ref(), Some(encoding));
+    }
+    assert_eq!(Encoding::from_name("ABC"), Some(Encoding::Tcvn3));
+}
+#[test]
+fn test_fix_mojibake() {
+    assert_eq!(fix_mojibake("Viá»‡t Nam, Ä\u{90}Ã\u{A0} Náºµng"), Some("Việt Nam, Đà Nẵng".to_owned()));
+    assert_eq!(fix_mojibake("Việt"), None);
+    assert_eq!(fix_mojibake("Vi\u{E1}t"), None);
+}
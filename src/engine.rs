@@ -0,0 +1,291 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+use edit::{ Edit, Unit };
+use input::Input;
+use input_method::{ InputMethod, KeyType };
+use util;
+use vword::{ VWord, Raw, Flag };
+
+/// A key event fed to an `Engine`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Event {
+    Key(char),
+    Backspace,
+    /// Commit the word being typed, eg: when the text field loses focus.
+    Commit,
+    /// Drop the word being typed.
+    Reset,
+}
+
+/// What an `Engine` reports after an event.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Update {
+    /// Text to commit to the application, before the preedit.
+    pub commit: String,
+    /// The word being typed.
+    pub preedit: String,
+    /// `false` if the event was not used and should go to the application,
+    /// eg: a backspace without a word being typed.
+    pub handled: bool,
+}
+
+/// A typing engine, fed key events one at a time.
+/// It keeps the keystrokes of the word being typed and the word they
+/// compose, which is recomputed from the keystrokes after a backspace.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    input_method: InputMethod,
+    keys: Vec<char>,
+    word: String,
+    stroke: Stroke,
+}
+
+// What the last keystroke of a word did.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Stroke {
+    Typed,
+    // Inserted an 'ư', see `Options::insert_uw`.
+    InsertedUw,
+    // Undid a modifier and appended the key, eg: the second 's' of "chauss".
+    Escaped,
+}
+
+impl Engine {
+    pub fn new(input_method: InputMethod) -> Engine {
+        Engine {
+            input_method: input_method,
+            keys: Vec::new(),
+            word: String::new(),
+            stroke: Stroke::Typed,
+        }
+    }
+    pub fn input_method(&self) -> &InputMethod { &self.input_method }
+/// Return the keystrokes of the word being typed.
+    pub fn keys(&self) -> &[char] { &self.keys }
+/// Return the word being typed.
+    pub fn preedit(&self) -> &str { &self.word }
+
+    pub fn feed(&mut self, event: Event) -> Update {
+        let mut commit = String::new();
+        let mut handled = true;
+        match event {
+            Event::Key(c) => {
+                if self.is_word_key(c) {
+                    self.keys.push(c);
+                    let (word, stroke) = type_key(&self.word, c, self.stroke,
+                                                  &self.input_method);
+                    self.word = word;
+                    self.stroke = stroke;
+                } else {
+                    commit = self.finish();
+                    commit.push(c);
+                }
+            },
+            Event::Backspace => {
+                if self.keys.pop().is_some() {
+                    let (word, stroke) = compose_keys(&self.keys, &self.input_method);
+                    self.word = word;
+                    self.stroke = stroke;
+                } else {
+                    handled = false;
+                }
+            },
+            Event::Commit => commit = self.finish(),
+            Event::Reset => { self.take(); },
+        }
+        Update {
+            commit: commit,
+            preedit: self.word.clone(),
+            handled: handled,
+        }
+    }
+
+/// Feed an event and return the edit from the text shown before it to
+/// the text shown after it, for front-ends which type the word into the
+/// application instead of showing a preedit.
+/// Return `None` if the event should go to the application.
+    pub fn feed_edit(&mut self, event: Event, unit: &Unit) -> Option<Edit> {
+        let old = self.word.clone();
+        let update = self.feed(event);
+        if update.handled {
+            Some(Edit::new(&old, &(update.commit + &update.preedit), unit))
+        } else {
+            None
+        }
+    }
+
+    // Return `true` if `c` is a part of the word rather than ending it.
+    // A key of the input method which is not a letter (eg: a VIQR mark)
+    // only belongs to a word which is already started.
+    fn is_word_key(&self, c: char) -> bool {
+        c.is_alphanumeric() || (self.input_method.is_key(c) && !self.keys.is_empty())
+    }
+    // Clear the word being typed and return it, or its keystrokes if they
+    // should be restored (see `Options::auto_restore`). A word which ends
+    // with an escape is kept when the escape is its only change, eg: "chauss"
+    // gives "chaus".
+    fn finish(&mut self) -> String {
+        let restore = {
+            let options = self.input_method.options();
+            let raw: String = self.keys.iter().cloned().collect();
+            options.auto_restore && raw != self.word
+            && !(self.stroke == Stroke::Escaped && self.unescaped_keys() == self.word)
+            && (options.english_words.contains(&raw.to_lowercase())
+                || !util::is_valid(&VWord::from_str(&self.word), &options.validation))
+        };
+        if restore {
+            self.word = self.keys.iter().cloned().collect();
+        }
+        self.take()
+    }
+    // Return the keystrokes without the key which the last one undid.
+    fn unescaped_keys(&self) -> String {
+        let mut keys = self.keys.clone();
+        if let Some((&last, init)) = self.keys.split_last() {
+            let same = |x: &char| x.to_lowercase().eq(last.to_lowercase());
+            if let Some(i) = init.iter().rposition(same) {
+                keys.remove(i);
+            }
+        }
+        keys.into_iter().collect()
+    }
+    // Clear the word being typed and return it.
+    fn take(&mut self) -> String {
+        self.keys.clear();
+        self.stroke = Stroke::Typed;
+        ::std::mem::replace(&mut self.word, String::new())
+    }
+}
+
+// Return `word` after typing `c`, and what `c` did.
+// The tone is moved to where it belongs in the new word, so it can be typed
+// anywhere in the word, eg: "tuyreen" gives "tuyển".
+// Typing the `uw` key again right after it inserted an 'ư' gives the key
+// instead, eg: "ww" gives "w", but "thuww" still gives "thuw".
+fn type_key(word: &str, c: char, last: Stroke, input_method: &InputMethod)
+            -> (String, Stroke) {
+    let is_uw_key = c.to_lowercase().next().map_or(false, |x| {
+        input_method.get_type(x).contains(&KeyType::Toggle(Raw::U, Flag::W))
+    });
+    if last == Stroke::InsertedUw && is_uw_key {
+        let mut word = word.to_owned();
+        word.pop();
+        word.push(c);
+        return (word, Stroke::Escaped);
+    }
+    let output = Input::new(word.to_owned(), c, input_method.clone()).output();
+    let (new_word, mut stroke) = match output {
+        // An undone modifier is appended as it was typed.
+        Ok(s) => {
+            let stroke = if s.ends_with(c) { Stroke::Escaped } else { Stroke::Typed };
+            (s, stroke)
+        },
+        Err(mut s) => {
+            s.push(c);
+            (s, Stroke::Typed)
+        },
+    };
+    if is_uw_key && (new_word == word.to_owned() + "ư" || new_word == word.to_owned() + "Ư") {
+        stroke = Stroke::InsertedUw;
+    }
+    let mut vword = VWord::from_str(&new_word);
+    if util::is_vietnamese(&vword) {
+        vword.fix_uo();
+        vword.replace_tone(input_method.options().tone_placement.as_ref());
+        (vword.to_string(), stroke)
+    } else {
+        (new_word, stroke)
+    }
+}
+
+/// Return the word typed by a sequence of keystrokes.
+pub fn compose(keys: &[char], input_method: &InputMethod) -> String {
+    compose_keys(keys, input_method).0
+}
+
+// Return the word typed by a sequence of keystrokes, and what the last one
+// did.
+fn compose_keys(keys: &[char], input_method: &InputMethod) -> (String, Stroke) {
+    keys.iter().fold((String::new(), Stroke::Typed), |(word, stroke), c| {
+        type_key(&word, *c, stroke, input_method)
+    })
+}
+
+/// Convert text typed without an input method, eg: "Xin chaof cacs banj."
+/// gives "Xin chào các bạn.". Words which do not make a Vietnamese word are
+/// kept as typed (see `Options::auto_restore`), and so are URLs and email
+/// addresses. Marks which end a word are typed as a part of it, eg: "Ho^`"
+/// gives "Hồ" in VIQR, except for the punctuation of a sentence (`.,;:!?`),
+/// eg: the period of "Nam." is not a nặng.
+pub fn convert(text: &str, input_method: &InputMethod) -> String {
+    let mut method = input_method.clone();
+    method.options_mut().auto_restore = true;
+    let mut engine = Engine::new(method.clone());
+    let mut ret = String::new();
+    let mut start = 0;
+    // Split `text` after every whitespace, so each part is a token and the
+    // whitespace which ends it.
+    let ends = text.char_indices().filter(|&(_, c)| c.is_whitespace())
+        .map(|(i, c)| i + c.len_utf8()).chain(Some(text.len()));
+    for end in ends {
+        let part = &text[start..end];
+        start = end;
+        let token = part.split_whitespace().next().unwrap_or("");
+        if is_link(token) {
+            ret.push_str(part);
+            continue;
+        }
+        let n: usize = token.chars().rev()
+            .take_while(|&c| {
+                !c.is_alphanumeric() && (!method.is_key(c) || ".,;:!?".contains(c))
+            })
+            .map(char::len_utf8).sum();
+        let word = &token[..token.len() - n];
+        for c in word.chars() {
+            ret.push_str(&engine.feed(Event::Key(c)).commit);
+        }
+        ret.push_str(&engine.feed(Event::Commit).commit);
+        ret.push_str(&part[word.len()..]);
+    }
+    ret
+}
+
+// Return `true` if `token` looks like a URL or an email address.
+fn is_link(token: &str) -> bool {
+    token.contains("://") || token.starts_with("www.") || token.contains('@')
+}
+
+#[test]
+fn test_compose() {
+    fn test(keys: &str, s: &str) {
+        let keys: Vec<char> = keys.chars().collect();
+        assert_eq!(compose(&keys, &InputMethod::telex()), s);
+    }
+    test("chaof", "chào"); test("Vieetj", "Việt");
+    test("dduwowngf", "đường"); test("chauss", "chaus");
+    test("hello", "hello"); test("w", "ư");
+    test("ww", "w"); test("nhww", "nhw"); test("NHWW", "NHW");
+    test("thuww", "thuw"); test("tww", "tw");
+}
+#[test]
+fn test_convert() {
+    fn test(text: &str, s: &str) {
+        assert_eq!(convert(text, &InputMethod::telex()), s);
+    }
+    test("Xin chaof cacs banj.", "Xin chào các bạn.");
+    test("  Tieengs Vieetj\n\tla2 ", "  Tiếng Việt\n\tla2 ");
+    test("Xem https://vi.wikipedia.org/wiki/Tieesng hoawcj www.vnexpress.net",
+         "Xem https://vi.wikipedia.org/wiki/Tieesng hoặc www.vnexpress.net");
+    test("Gui73 cho tooi: ai@example.com", "Gui73 cho tôi: ai@example.com");
+    test("windows, Mac (vaf Linux)!", "windows, Mac (và Linux)!");
+    test("", "");
+
+    let viqr = InputMethod::viqr();
+    assert_eq!(convert("To^i ho.c tie^'ng Anh. Vie^.t Nam.", &viqr),
+               "Tôi học tiếng Anh. Việt Nam.");
+    assert_eq!(convert("Tôi học tiếng Anh. Kho^ng?", &viqr), "Tôi học tiếng Anh. Không?");
+    assert_eq!(convert("Ba'c Ho^` ma' cu?a ha` no^.i, nha`. (Ddi chu+a?)", &viqr),
+               "Bác Hồ má của hà nội, nhà. (Đi chưa?)");
+}
@@ -3,21 +3,24 @@
 // <http://opensource.org/licenses/MIT>
 
 use util;
-use input_method::InputMethod;
+use util::SpellingError;
+use input_method::{ InputMethod, Options };
 use input_method::KeyType;
 use rustc_serialize::json;
-use vword::{ VWord, VChar, VResult };
+use vword::{ VWord, VChar, VResult, Raw, Flag, Tone };
 
 #[derive(Debug, PartialEq)]
 #[derive(RustcDecodable, RustcEncodable)]
 pub struct Input {
     word: String,
     modifier: char,
+    upcase: bool,
     input_method: InputMethod,
 }
 
 impl Input {
     pub fn new(a: String, mut b: char, c: InputMethod) -> Input {
+        let upcase = b.is_uppercase();
         let b_lower: Vec<_> = b.to_lowercase().collect();
         if b_lower.len() > 1 {
             b = '\0';
@@ -27,79 +30,165 @@ impl Input {
         Input {
             word: a,
             modifier: b,
+            upcase: upcase,
             input_method: c,
         }
     }
 
 /// Get output of the input - the most important function of the
 /// crate.
-/// This function return a new string as a replacement for `Input.word`.
-/// If the `Input.word` is not a Vietnamese string, return `None`.
+/// This function return a new string as a replacement for `Input.word`,
+/// the modifier is consumed.
+/// If the modifier does not change `Input.word`, eg: `Input.word` is not
+/// a Vietnamese string, return `Err` and the caller should append it.
+/// Pressing a modifier again undoes it and appends it literally
+/// ("cháu" + 's' gives "chaus"), then the word is left as is.
     pub fn output(&self) -> Result<String, String> {
-        let all = VWord::from_str(&self.word);
-        let (mut word, rest) = {
-            let last_non_alphabet_index = all.iter().enumerate()
-                .fold(-1i32, |mut res, (i, (ref x, _))| {
-                    if let VChar::Invalid(c) = **x {
-                        if !c.is_alphabetic() && !c.is_digit(10) {
-                            res = i as i32;
-                        }
-                    }
-                    res
-                });
-
-            let word: Vec<_> = all.iter()
-                .skip((last_non_alphabet_index+1) as usize).collect();
-            let (x, y) = word.into_iter()
-                .fold((Vec::new(), Vec::new()), |mut res, (x, y)| {
-                    (res.0).push(x.clone());
-                    (res.1).push(y.clone());
-                    res
-                });
-            let word: Vec<_> = all.iter().take((last_non_alphabet_index+1) as usize)
-                .collect();
-            let (z, t) = word.into_iter()
-                .fold((Vec::new(), Vec::new()), |mut res, (x, y)| {
-                    (res.0).push(x.clone());
-                    (res.1).push(y.clone());
-                    res
-                });
-            (VWord::new_raw(x, y), VWord::new_raw(z, t))
-        };
-
-
-
-        if util::is_vietnamese(&word) {
-
+        let (mut word, rest) = self.split();
+        let options = self.input_method.options();
+        if (!options.spell_check || util::is_vietnamese(&word))
+        && !is_escaped(&word, &self.input_method) {
+            let uw = KeyType::Toggle(Raw::U, Flag::W);
             let key_types = self.input_method.get_type(self.modifier);
             for x in &key_types {
-                let mut process = |key_type: &KeyType| {
-                    match *key_type {
-                        KeyType::None => (VResult::None, word.to_string()),
-                        KeyType::Toggle(ref x, ref y) => {
-                            (word.toggle_vovel(x, y), word.to_string())
-                        },
-                        KeyType::Tone(ref x) => (word.toggle_tone(x), word.to_string()),
-                        KeyType::ToggleD => (word.toggle_d(), word.to_string()),
-                    }
-                };
-
-                match process(x) {
-                    (VResult::Set, s) => return Ok(rest.to_string() + &s),
-                    (VResult::Unset, s) => return Err(rest.to_string() + &s),
-                    _ => {}
+                match apply(&mut word, x, options) {
+                    VResult::Set => return Ok(rest.to_string() + &word.to_string()),
+                    VResult::Unset => {
+                        return Ok(rest.to_string() + &word.to_string() + &self.literal());
+                    },
+                    VResult::None => {}
                 }
             }
+            if options.insert_uw && key_types.contains(&uw)
+            && word.vchars().iter().all(|x| if let VChar::Consonant(_) = *x {true} else {false}) {
+                word.push(VChar::Vovel(Raw::U, Flag::W, Tone::N), self.upcase);
+                return Ok(rest.to_string() + &word.to_string());
+            }
             Err(rest.to_string() + &word.to_string())
         } else {
             Err(rest.to_string() + &word.to_string())
         }
     }
+/// Return why the spell check rejects the word the modifier applies to,
+/// eg: for a debug overlay. Return `Ok(())` if the word passes or if
+/// `Options::spell_check` is off.
+    pub fn check(&self) -> Result<(), SpellingError> {
+        if self.input_method.options().spell_check {
+            util::check_vietnamese(&self.split().0)
+        } else {
+            Ok(())
+        }
+    }
+    // Split `word` into the word the modifier applies to, and the text
+    // before it.
+    fn split(&self) -> (VWord, VWord) {
+        let all = VWord::from_str(&self.word);
+        // A key of the input method which follows a letter (eg: a literal
+        // VIQR mark) belongs to the word rather than splitting it.
+        let last_non_alphabet_index = all.iter().enumerate()
+            .fold(-1i32, |mut res, (i, (ref x, _))| {
+                if let VChar::Invalid(c) = **x {
+                    if !c.is_alphabetic() && !c.is_digit(10)
+                    && !(self.input_method.is_key(c) && res + 1 < i as i32) {
+                        res = i as i32;
+                    }
+                }
+                res
+            });
+
+        let word: Vec<_> = all.iter()
+            .skip((last_non_alphabet_index+1) as usize).collect();
+        let (x, y) = word.into_iter()
+            .fold((Vec::new(), Vec::new()), |mut res, (x, y)| {
+                (res.0).push(x.clone());
+                (res.1).push(y.clone());
+                res
+            });
+        let word: Vec<_> = all.iter().take((last_non_alphabet_index+1) as usize)
+            .collect();
+        let (z, t) = word.into_iter()
+            .fold((Vec::new(), Vec::new()), |mut res, (x, y)| {
+                (res.0).push(x.clone());
+                (res.1).push(y.clone());
+                res
+            });
+        (VWord::new_raw(x, y), VWord::new_raw(z, t))
+    }
+    // Return the modifier as it was typed.
+    fn literal(&self) -> String {
+        if self.upcase {
+            self.modifier.to_uppercase().collect()
+        } else {
+            self.modifier.to_string()
+        }
+    }
     pub fn decode(s: &str) -> Result<Input, json::DecoderError> {
         json::decode::<Input>(s)
     }
 }
 
+fn apply(word: &mut VWord, key_type: &KeyType, options: &Options) -> VResult {
+    match *key_type {
+        KeyType::None => VResult::None,
+        KeyType::Toggle(ref x, ref y) => word.toggle_vovel(x, y),
+        KeyType::Tone(ref x) => match options.tone_placement {
+            Some(ref placement) => word.toggle_tone_with(x, placement),
+            None => word.toggle_tone(x),
+        },
+        KeyType::ToggleD => word.toggle_d(),
+        KeyType::RemoveTone => word.remove_tone(),
+        KeyType::RemoveAll => word.remove_diacritics(),
+    }
+}
+
+// Return `true` if a modifier was escaped in `word`, ie: a key follows
+// letters it would have changed, as in "chaus" or "caa".
+fn is_escaped(word: &VWord, input_method: &InputMethod) -> bool {
+    let (data, upcase): (Vec<_>, Vec<_>) = word.iter()
+        .map(|(x, y)| (x.clone(), *y)).unzip();
+    let double_o = is_double_o(word);
+    for i in 1..data.len() {
+        let c = match data[i] {
+            VChar::Vovel(_, Flag::N, Tone::N) | VChar::Consonant(_) | VChar::Invalid(_) => {
+                data[i].to_char()
+            },
+            _ => continue,
+        };
+        if double_o && data[i-1] == data[i] && data[i] == VChar::Vovel(Raw::O, Flag::N, Tone::N) {
+            continue;
+        }
+        for key_type in &input_method.get_type(c) {
+            let mut prefix = VWord::new_raw(data[..i].to_vec(), upcase[..i].to_vec());
+            if apply(&mut prefix, key_type, input_method.options()) != VResult::None {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+// Return `true` if the vovels of `word` are a double 'o' followed by the
+// final consonant 'c' or 'ng', as in "xoong", "moóc". This 'oo' is typed
+// 'ooo', and is not escaped once the word is complete.
+fn is_double_o(word: &VWord) -> bool {
+    let word = word.to_lowercase();
+    let data = word.vchars();
+    let is_vovel = |x: &VChar| if let VChar::Vovel(_, _, _) = *x {true} else {false};
+    let mut start = data.iter().position(|x| is_vovel(x)).unwrap_or(data.len());
+    let end = data.iter().rposition(|x| is_vovel(x)).map_or(start, |x| x + 1);
+    // The 'i' of 'gi' is a part of the consonant: "giooc".
+    if start > 0 && start + 1 < end && data[start - 1] == VChar::Consonant('g')
+    && data[start] == VChar::Vovel(Raw::I, Flag::N, Tone::N) {
+        start += 1;
+    }
+    let double_o = end == start + 2 && data[start..end].iter().all(|x| match *x {
+        VChar::Vovel(Raw::O, Flag::N, _) => true,
+        _ => false,
+    });
+    let coda: String = data[end..].iter().map(|x| x.to_char()).collect();
+    double_o && (coda == "c" || coda == "ng")
+}
+
 #[test]
 fn encode_decode() {
     let x = Input::new("text".to_owned(), 'c', InputMethod::telex());
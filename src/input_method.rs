@@ -2,67 +2,241 @@
 // Licensed under the MIT license, see the LICENSE file or
 // <http://opensource.org/licenses/MIT>
 
-use vword::{ Raw, Flag, Tone };
+use std::collections::{ BTreeMap, BTreeSet };
+use util::Validation;
+use vword::{ Raw, Flag, Tone, TonePlacement };
 
+/// An input method is a table from a key to the actions it triggers.
+/// A key may trigger several actions (eg: 'w' in Telex), and an action
+/// may be bound to several keys.
 #[derive(Debug, PartialEq, Clone)]
 #[derive(RustcDecodable, RustcEncodable)]
 pub struct InputMethod {
-    aa: char,
-    aw: char,
-    ee: char,
-    oo: char,
-    ow: char,
-    uw: char,
-    dd: char,
-    s: char,
-    f: char,
-    r: char,
-    x: char,
-    j: char,
+    keys: BTreeMap<char, Vec<KeyType>>,
+    options: Options,
 }
 
+/// Options which change how an input method processes a word.
+#[derive(Debug, PartialEq, Clone)]
+#[derive(RustcDecodable, RustcEncodable)]
+pub struct Options {
+    /// Only transform words which look like Vietnamese (`util::is_vietnamese`).
+    pub spell_check: bool,
+    /// Insert 'ư' when a key bound to `uw` finds no vovel to change,
+    /// eg: 'w' or 'nhw' in Telex. `Engine` gives the key back when it is
+    /// typed again right after, eg: "ww" gives "w".
+    pub insert_uw: bool,
+    /// Let `Engine` bring back the keystrokes of a committed word when
+    /// they do not make a Vietnamese word, eg: "address" instead of "ađress".
+    /// A word whose only change is an escape is kept, eg: "chauss" gives "chaus".
+    pub auto_restore: bool,
+    /// Words which `auto_restore` always brings back, even when their
+    /// keystrokes make a Vietnamese word (eg: "mix"). Stored in lowercase.
+    pub english_words: BTreeSet<String>,
+    /// Where tones go in 'oa', 'oe' and 'uy', see `TonePlacement`. `None`
+    /// keeps the placement of `VWord::toggle_tone`: hoà, khoẻ, thúy.
+    pub tone_placement: Option<TonePlacement>,
+    /// How `auto_restore` checks a committed word. `spell_check` is always
+    /// lenient, as the word is not complete yet.
+    pub validation: Validation,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            spell_check: true,
+            insert_uw: false,
+            auto_restore: false,
+            english_words: BTreeSet::new(),
+            tone_placement: None,
+            validation: Validation::Lenient,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[derive(RustcDecodable, RustcEncodable)]
 pub enum KeyType {
     None,
     Toggle(Raw, Flag),
     Tone(Tone),
     ToggleD,
+    /// Remove the tone of the word.
+    RemoveTone,
+    /// Remove every diacritic of the word: tone, flags and 'đ'.
+    RemoveAll,
+}
+
+impl KeyType {
+/// Return the action of a name, the names are the ones of the Telex keys:
+/// "aa", "aw", "ee", "oo", "ow", "uw", "dd", "s", "f", "r", "x", "j",
+/// then "remove_tone" and "remove_all".
+    pub fn from_name(name: &str) -> Option<KeyType> {
+        Some(match name {
+            "aa" => KeyType::Toggle(Raw::A, Flag::D),
+            "aw" => KeyType::Toggle(Raw::A, Flag::W),
+            "ee" => KeyType::Toggle(Raw::E, Flag::D),
+            "oo" => KeyType::Toggle(Raw::O, Flag::D),
+            "ow" => KeyType::Toggle(Raw::O, Flag::W),
+            "uw" => KeyType::Toggle(Raw::U, Flag::W),
+            "dd" => KeyType::ToggleD,
+            "s" => KeyType::Tone(Tone::S),
+            "f" => KeyType::Tone(Tone::F),
+            "r" => KeyType::Tone(Tone::R),
+            "x" => KeyType::Tone(Tone::X),
+            "j" => KeyType::Tone(Tone::J),
+            "remove_tone" => KeyType::RemoveTone,
+            "remove_all" => KeyType::RemoveAll,
+            _ => return None,
+        })
+    }
+/// Return the name of an action, see `KeyType::from_name`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            KeyType::None => "none",
+            KeyType::Toggle(Raw::A, Flag::D) => "aa",
+            KeyType::Toggle(Raw::A, Flag::W) => "aw",
+            KeyType::Toggle(Raw::E, Flag::D) => "ee",
+            KeyType::Toggle(Raw::O, Flag::D) => "oo",
+            KeyType::Toggle(Raw::O, Flag::W) => "ow",
+            KeyType::Toggle(Raw::U, Flag::W) => "uw",
+            KeyType::Toggle(_, _) => "toggle",
+            KeyType::ToggleD => "dd",
+            KeyType::Tone(Tone::S) => "s",
+            KeyType::Tone(Tone::F) => "f",
+            KeyType::Tone(Tone::R) => "r",
+            KeyType::Tone(Tone::X) => "x",
+            KeyType::Tone(Tone::J) => "j",
+            KeyType::Tone(Tone::N) => "none",
+            KeyType::RemoveTone => "remove_tone",
+            KeyType::RemoveAll => "remove_all",
+        }
+    }
 }
 
 impl InputMethod {
-    pub fn new(arr: [char; 12]) -> InputMethod {
+/// Return an input method without any key.
+    pub fn empty() -> InputMethod {
         InputMethod {
-            aa: arr[0],
-            aw: arr[1],
-            ee: arr[2],
-            oo: arr[3],
-            ow: arr[4],
-            uw: arr[5],
-            dd: arr[6],
-            s: arr[7],
-            f: arr[8],
-            r: arr[9],
-            x: arr[10],
-            j: arr[11],
+            keys: BTreeMap::new(),
+            options: Options::default(),
+        }
+    }
+/// Return an input method with one key for each action, in the order:
+/// aa, aw, ee, oo, ow, uw, dd, s, f, r, x, j.
+    pub fn new(arr: [char; 12]) -> InputMethod {
+        let actions = [
+            KeyType::Toggle(Raw::A, Flag::D), KeyType::Toggle(Raw::A, Flag::W),
+            KeyType::Toggle(Raw::E, Flag::D), KeyType::Toggle(Raw::O, Flag::D),
+            KeyType::Toggle(Raw::O, Flag::W), KeyType::Toggle(Raw::U, Flag::W),
+            KeyType::ToggleD,
+            KeyType::Tone(Tone::S), KeyType::Tone(Tone::F), KeyType::Tone(Tone::R),
+            KeyType::Tone(Tone::X), KeyType::Tone(Tone::J),
+        ];
+        let mut ret = InputMethod::empty();
+        for (c, action) in arr.iter().zip(actions.iter()) {
+            ret.bind(*c, action.clone());
+        }
+        ret
+    }
+/// Return a built-in input method by its name: "telex", "vni" or "viqr".
+    pub fn by_name(name: &str) -> Option<InputMethod> {
+        match name {
+            "telex" => Some(InputMethod::telex()),
+            "vni" => Some(InputMethod::vni()),
+            "viqr" => Some(InputMethod::viqr()),
+            _ => None,
         }
     }
     pub fn telex() -> InputMethod {
-        InputMethod::new(['a', 'w', 'e', 'o', 'w', 'w', 'd', 's', 'f', 'r', 'x', 'j'])
+        let mut ret = InputMethod::new(
+            ['a', 'w', 'e', 'o', 'w', 'w', 'd', 's', 'f', 'r', 'x', 'j']
+        );
+        ret.bind('z', KeyType::RemoveTone);
+        ret.options.insert_uw = true;
+        ret
+    }
+    pub fn vni() -> InputMethod {
+        let mut ret = InputMethod::new(
+            ['6', '8', '6', '6', '7', '7', '9', '1', '2', '3', '4', '5']
+        );
+        ret.bind('0', KeyType::RemoveTone);
+        ret
+    }
+    pub fn viqr() -> InputMethod {
+        InputMethod::new(['^', '(', '^', '^', '+', '+', 'd', '\'', '`', '?', '~', '.'])
+    }
+/// Bind `action` to `key`, after the actions `key` already has.
+/// Keys are case insensitive.
+    pub fn bind(&mut self, key: char, action: KeyType) {
+        if action == KeyType::None {
+            return;
+        }
+        let key = key.to_lowercase().next().unwrap_or(key);
+        let actions = self.keys.entry(key).or_insert_with(Vec::new);
+        if !actions.contains(&action) {
+            actions.push(action);
+        }
+    }
+/// Remove every action of `key`.
+    pub fn unbind(&mut self, key: char) {
+        let key = key.to_lowercase().next().unwrap_or(key);
+        self.keys.remove(&key);
+    }
+/// Add every binding of `other` to `self`. Eg: Telex and VNI together.
+    pub fn merge(&mut self, other: &InputMethod) {
+        for (key, actions) in &other.keys {
+            for action in actions {
+                self.bind(*key, action.clone());
+            }
+        }
+    }
+    pub fn options(&self) -> &Options { &self.options }
+    pub fn options_mut(&mut self) -> &mut Options { &mut self.options }
+/// Return `true` if `c` is bound to some action.
+    pub fn is_key(&self, c: char) -> bool {
+        self.keys.contains_key(&c)
     }
+/// Return a key bound to `action`, preferring the keys with fewer actions,
+/// eg: '[' rather than 'w' for `uw` if both are bound.
+    pub fn key_for(&self, action: &KeyType) -> Option<char> {
+        self.keys.iter().filter(|&(_, actions)| actions.contains(action))
+            .min_by_key(|&(_, actions)| actions.len())
+            .map(|(key, _)| *key)
+    }
+/// Return the actions of `c` in the order they are tried,
+/// followed by `KeyType::None`.
     pub fn get_type(&self, c: char) -> Vec<KeyType> {
-        let mut ret = Vec::new();
-        if c == self.aa { ret.push(KeyType::Toggle(Raw::A, Flag::D)); }
-        if c == self.aw { ret.push(KeyType::Toggle(Raw::A, Flag::W)); }
-        if c == self.ee { ret.push(KeyType::Toggle(Raw::E, Flag::D)); }
-        if c == self.oo { ret.push(KeyType::Toggle(Raw::O, Flag::D)); }
-        if c == self.ow { ret.push(KeyType::Toggle(Raw::O, Flag::W)); }
-        if c == self.uw { ret.push(KeyType::Toggle(Raw::U, Flag::W)); }
-        if c == self.dd { ret.push(KeyType::ToggleD); }
-        if c == self.s { ret.push(KeyType::Tone(Tone::S)); }
-        if c == self.f { ret.push(KeyType::Tone(Tone::F)); }
-        if c == self.r { ret.push(KeyType::Tone(Tone::R)); }
-        if c == self.x { ret.push(KeyType::Tone(Tone::X)); }
-        if c == self.j { ret.push(KeyType::Tone(Tone::J)); }
+        let mut ret = match self.keys.get(&c) {
+            Some(actions) => actions.clone(),
+            None => Vec::new(),
+        };
         ret.push(KeyType::None);
         ret
     }
 }
+
+#[test]
+fn test_get_type() {
+    let telex = InputMethod::telex();
+    assert_eq!(telex.get_type('w'), vec![
+        KeyType::Toggle(Raw::A, Flag::W), KeyType::Toggle(Raw::O, Flag::W),
+        KeyType::Toggle(Raw::U, Flag::W), KeyType::None,
+    ]);
+    assert_eq!(telex.get_type('s'), vec![KeyType::Tone(Tone::S), KeyType::None]);
+    assert_eq!(telex.get_type('b'), vec![KeyType::None]);
+
+    let mut x = InputMethod::telex();
+    x.merge(&InputMethod::vni());
+    x.bind('[', KeyType::Toggle(Raw::U, Flag::W));
+    x.bind('S', KeyType::Tone(Tone::S));
+    assert_eq!(x.get_type('s'), vec![KeyType::Tone(Tone::S), KeyType::None]);
+    assert_eq!(x.get_type('1'), vec![KeyType::Tone(Tone::S), KeyType::None]);
+    assert_eq!(x.get_type('['), vec![KeyType::Toggle(Raw::U, Flag::W), KeyType::None]);
+    assert_eq!(x.key_for(&KeyType::Toggle(Raw::U, Flag::W)), Some('['));
+    assert_eq!(x.key_for(&KeyType::Toggle(Raw::O, Flag::W)), Some('7'));
+    x.unbind('[');
+    assert!(!x.is_key('['));
+    assert_eq!(x.key_for(&KeyType::Toggle(Raw::U, Flag::W)), Some('7'));
+    assert_eq!(x.key_for(&KeyType::RemoveAll), None);
+}
@@ -0,0 +1,144 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+//! Turn Vietnamese text back into the keys which type it, the reverse of
+//! `engine::compose`. Eg: "Tiếng Việt" is "Tieengs Vieetj" in Telex and
+//! "Tie6ng1 Vie6t5" in VNI.
+
+use engine::compose;
+use input_method::{ InputMethod, KeyType };
+use vword::{ VWord, VChar, Flag, Tone };
+
+/// Where the key of the tone is typed.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ToneStyle {
+    /// At the end of the word: "Vieetj".
+    EndOfWord,
+    /// Right after the vovel which takes it: "Vieejt".
+    AfterVovel,
+}
+
+/// Return the keys which type `text` with `input_method`.
+/// A key which would change the word instead of being typed as is (eg: the
+/// second 'o' of "xoong" in Telex) is pressed twice, and a character without
+/// a key for its diacritics is typed as is.
+pub fn keystrokes(text: &str, input_method: &InputMethod, style: &ToneStyle) -> String {
+    let mut ret = String::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() || (input_method.is_key(c) && !word.is_empty()) {
+            word.push(c);
+        } else {
+            ret.push_str(&word_keystrokes(&word, input_method, style));
+            word.clear();
+            ret.push(c);
+        }
+    }
+    ret.push_str(&word_keystrokes(&word, input_method, style));
+    ret
+}
+
+// Return the keys which type `word`, which has no separator.
+fn word_keystrokes(word: &str, input_method: &InputMethod, style: &ToneStyle) -> String {
+    let vword = VWord::from_str(word);
+    let toned: Vec<usize> = vword.vchars().iter().enumerate()
+        .filter(|&(_, x)| match *x {
+            VChar::Vovel(_, _, ref tone) => *tone != Tone::N,
+            _ => false,
+        })
+        .map(|(i, _)| i).collect();
+    // Several tones are only typed after their vovels.
+    let at_end = *style == ToneStyle::EndOfWord && toned.len() == 1;
+
+    let mut keys: Vec<char> = Vec::new();
+    let case = |c: char, up: bool| if up { c.to_uppercase().next().unwrap_or(c) } else { c };
+    // Type `c` as is, pressing it again if it changed the word. The tone
+    // may move: "bóa" + 'c' is "boác".
+    // Return `true` if it was pressed again.
+    let literal = |keys: &mut Vec<char>, c: char| -> bool {
+        let expected = VWord::from_str(&(compose(keys, input_method) + &c.to_string()));
+        keys.push(c);
+        let typed = VWord::from_str(&compose(keys, input_method));
+        if typed.tone() != expected.tone() || without_tone(typed) != without_tone(expected) {
+            keys.push(c);
+            true
+        } else {
+            false
+        }
+    };
+    let mut tone_key = None;
+    // The word is escaped by a key pressed twice, the tone can only be typed
+    // at the end, where "xoong" takes it.
+    let mut escaped = false;
+    for (x, &up) in vword.iter() {
+        match *x {
+            VChar::Vovel(ref raw, ref flag, ref tone) => {
+                let flag_key = if *flag == Flag::N {
+                    None
+                } else {
+                    input_method.key_for(&KeyType::Toggle(raw.clone(), flag.clone()))
+                };
+                let key = if *tone == Tone::N {
+                    None
+                } else {
+                    input_method.key_for(&KeyType::Tone(tone.clone()))
+                };
+                // Type what has no key as is.
+                let base = VChar::Vovel(
+                    raw.clone(),
+                    if *flag == Flag::N || flag_key.is_some() { Flag::N } else { flag.clone() },
+                    if *tone == Tone::N || key.is_some() { Tone::N } else { tone.clone() },
+                );
+                escaped |= literal(&mut keys, case(base.to_char(), up));
+                if let Some(c) = flag_key {
+                    keys.push(case(c, up));
+                }
+                if let Some(c) = key {
+                    if at_end || escaped {
+                        tone_key = Some(case(c, up));
+                    } else {
+                        keys.push(case(c, up));
+                    }
+                }
+            },
+            VChar::Consonant('đ') => {
+                match input_method.key_for(&KeyType::ToggleD) {
+                    Some(c) => {
+                        escaped |= literal(&mut keys, case('d', up));
+                        keys.push(case(c, up));
+                    },
+                    None => escaped |= literal(&mut keys, case('đ', up)),
+                }
+            },
+            _ => escaped |= literal(&mut keys, case(x.to_char(), up)),
+        }
+    }
+    keys.extend(tone_key);
+    keys.into_iter().collect()
+}
+
+fn without_tone(mut word: VWord) -> VWord {
+    word.remove_tone();
+    word
+}
+
+#[test]
+fn test_keystrokes() {
+    fn test(text: &str, method: &InputMethod, style: ToneStyle, keys: &str) {
+        assert_eq!((text, keystrokes(text, method, &style).as_str()), (text, keys));
+    }
+    let telex = InputMethod::telex();
+    let vni = InputMethod::vni();
+    test("Tiếng Việt", &telex, ToneStyle::EndOfWord, "Tieengs Vieetj");
+    test("Tiếng Việt", &vni, ToneStyle::EndOfWord, "Tie6ng1 Vie6t5");
+    test("Tiếng Việt", &telex, ToneStyle::AfterVovel, "Tieesng Vieejt");
+    test("Tiếng Việt", &vni, ToneStyle::AfterVovel, "Tie61ng Vie65t");
+    test("ĐƯỜNG", &telex, ToneStyle::EndOfWord, "DDUWOWNGF");
+    test("thuở", &telex, ToneStyle::EndOfWord, "thuowr");
+    test("xoóng", &telex, ToneStyle::EndOfWord, "xooongs");
+    test("Xin chào, bạn!", &vni, ToneStyle::EndOfWord, "Xin chao2, ban5!");
+    test("windows", &telex, ToneStyle::EndOfWord, "wwindows");
+    test("A4", &vni, ToneStyle::EndOfWord, "A44");
+    test("Không?", &InputMethod::viqr(), ToneStyle::EndOfWord, "Kho^ng??");
+}
@@ -8,7 +8,15 @@ extern crate rustc_serialize;
 #[macro_use]
 extern crate lazy_static;
 
+pub mod collation;
+pub mod config;
+pub mod detect;
+pub mod edit;
+pub mod encoding;
+pub mod engine;
 pub mod input;
 pub mod input_method;
+pub mod keystrokes;
+pub mod syllable;
 pub mod util;
 pub mod vword;
@@ -0,0 +1,139 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+extern crate vntyper;
+
+use std::env;
+use std::io;
+use std::io::{ Read, Write };
+use std::process;
+use vntyper::detect;
+use vntyper::encoding::Encoding;
+use vntyper::engine;
+use vntyper::input_method::InputMethod;
+
+const USAGE: &'static str = "\
+Usage: vntyper <command> [options]
+
+Commands:
+    convert     Read text typed without an input method from the standard
+                input and write it in Vietnamese, eg: \"Xin chaof\" gives
+                \"Xin chào\".
+    recode      Read text in one encoding from the standard input and write
+                it in another, eg: a document typed with a VNI font in
+                UTF-8 with `-f vni`.
+    detect      Read text from the standard input and write the encodings it
+                may be in, from the most likely, with their confidence.
+
+Options:
+    -m <name>   The input method: telex (default), vni, viqr, or the name of
+                a JSON file in the configuration directory.
+    -c <dir>    The configuration directory (default: the current one).
+    -f <name>   The encoding to read: utf-8 (default), tcvn3 (or abc), vni,
+                viscii, windows-1258.
+    -t <name>   The encoding to write, the same names as `-f`.
+    -h          Print this message.
+";
+
+// Print `message` and the usage, then exit.
+fn fail(message: &str) -> ! {
+    let _ = writeln!(io::stderr(), "vntyper: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+// Convert `text` typed with the input method `method`, which may be a file
+// in the configuration directory `dir`.
+fn convert(text: &str, method: &str, dir: &str) -> Result<String, String> {
+    let method = try!(InputMethod::load(method, dir).map_err(|e| e.to_string()));
+    Ok(engine::convert(text, &method))
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match args.first() {
+        Some(x) if x == "-h" || x == "--help" => {
+            print!("{}", USAGE);
+            return;
+        },
+        Some(x) => x.clone(),
+        None => fail("missing command"),
+    };
+
+    let mut method = "telex".to_owned();
+    let mut dir = ".".to_owned();
+    let mut from = "utf-8".to_owned();
+    let mut to = "utf-8".to_owned();
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return;
+            },
+            "-m" | "-c" | "-f" | "-t" => match iter.next() {
+                Some(x) => x.clone(),
+                None => fail(&format!("missing value for `{}`", arg)),
+            },
+            _ => fail(&format!("unknown option `{}`", arg)),
+        };
+        match arg.as_str() {
+            "-m" => method = value,
+            "-c" => dir = value,
+            "-f" => from = value,
+            _ => to = value,
+        }
+    }
+
+    match command.as_str() {
+        "convert" => {
+            let mut text = String::new();
+            if let Err(e) = io::stdin().read_to_string(&mut text) {
+                fail(&e.to_string());
+            }
+            match convert(&text, &method, &dir) {
+                Ok(x) => print!("{}", x),
+                Err(e) => fail(&e),
+            }
+        },
+        "recode" => {
+            let encoding = |name: &str| match Encoding::from_name(name) {
+                Some(x) => x,
+                None => fail(&format!("unknown encoding `{}`", name)),
+            };
+            let (from, to) = (encoding(&from), encoding(&to));
+            let mut bytes = Vec::new();
+            if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+                fail(&e.to_string());
+            }
+            let result = from.decode(&bytes).and_then(|x| to.encode(&x));
+            match result {
+                Ok(x) => {
+                    if let Err(e) = io::stdout().write_all(&x) {
+                        fail(&e.to_string());
+                    }
+                },
+                Err(e) => fail(&e.to_string()),
+            }
+        },
+        "detect" => {
+            let mut bytes = Vec::new();
+            if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+                fail(&e.to_string());
+            }
+            for x in detect::detect(&bytes) {
+                println!("{:.3}  {}", x.confidence, x.source.name());
+            }
+        },
+        _ => fail(&format!("unknown command `{}`", command)),
+    }
+}
+
+#[test]
+fn test_convert() {
+    assert_eq!(convert("Xin chaof cacs banj.\n", "telex", "."),
+               Ok("Xin chào các bạn.\n".to_owned()));
+    assert_eq!(convert("Ba'c Ho^` ma' cu?a nha`.", "viqr", "."),
+               Ok("Bác Hồ má của nhà.".to_owned()));
+    assert!(convert("chaof", "nope", ".").is_err());
+}
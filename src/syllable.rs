@@ -0,0 +1,242 @@
+// Copyright 2016 Do Duy.
+// Licensed under the MIT license, see the LICENSE file or
+// <http://opensource.org/licenses/MIT>
+
+use std::fmt;
+use util;
+use vword::{ VWord, VChar, Raw, Flag, Tone, TonePlacement };
+
+/// A Vietnamese syllable split into its parts, eg: "thuyền" is
+/// "th" + "u" + "yê" + "n" with `Tone::F`.
+/// Every part keeps the case of its letters, the vovels carry no tone.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Syllable {
+    /// Phụ âm đầu, eg: "th", "ngh", "gi", "q". May be empty.
+    pub initial: VWord,
+    /// Âm đệm: the 'o' of "hoa", the 'u' of "thuý" or "qua". May be empty.
+    pub glide: VWord,
+    /// Âm chính: one vovel, or a double vovel (iê, yê, ia, ya, uô, ua,
+    /// ươ, ưa, oo).
+    pub nucleus: VWord,
+    /// Âm cuối: a consonant cluster or a vovel (i, y, o, u). May be empty.
+    pub coda: VWord,
+    pub tone: Tone,
+}
+
+// The double vovels which form a nucleus, and whether they take the tone on
+// their first vovel.
+const DOUBLE_VOVELS: [(Raw, Flag, Raw, Flag, bool); 9] = [
+    (Raw::I, Flag::N, Raw::E, Flag::D, false), // iê
+    (Raw::Y, Flag::N, Raw::E, Flag::D, false), // yê
+    (Raw::U, Flag::N, Raw::O, Flag::D, false), // uô
+    (Raw::U, Flag::W, Raw::O, Flag::W, false), // ươ
+    (Raw::O, Flag::N, Raw::O, Flag::N, false), // oo
+    (Raw::I, Flag::N, Raw::A, Flag::N, true), // ia
+    (Raw::Y, Flag::N, Raw::A, Flag::N, true), // ya
+    (Raw::U, Flag::N, Raw::A, Flag::N, true), // ua
+    (Raw::U, Flag::W, Raw::A, Flag::N, true), // ưa
+];
+
+// Return the raw vovel and flag of `x`, if it is a vovel.
+fn vovel(x: Option<&VChar>) -> Option<(Raw, Flag)> {
+    match x {
+        Some(&VChar::Vovel(ref raw, ref flag, _)) => Some((raw.clone(), flag.clone())),
+        _ => None,
+    }
+}
+
+impl Syllable {
+/// Split `word` into its parts.
+/// Return `None` if `word` is not shaped like a syllable, eg: it has an
+/// unknown consonant cluster, more than one tone or too many vovels.
+/// The spelling rules (eg: "k" before "e") are not checked.
+    pub fn parse(word: &VWord) -> Option<Syllable> {
+        let mut tone = Tone::N;
+        let mut data = Vec::new();
+        let mut upcase = Vec::new();
+        for (x, up) in word.iter() {
+            match *x {
+                VChar::Invalid(_) => return None,
+                VChar::Vovel(ref raw, ref flag, ref t) => {
+                    if *t != Tone::N {
+                        if tone != Tone::N {
+                            return None;
+                        }
+                        tone = t.clone();
+                    }
+                    data.push(VChar::Vovel(raw.clone(), flag.clone(), Tone::N));
+                },
+                _ => data.push(x.clone()),
+            }
+            upcase.push(*up);
+        }
+
+        let mut i = 0;
+        let part = |i: &mut usize, n: usize| {
+            let ret = VWord::new_raw(data[*i..*i+n].to_vec(), upcase[*i..*i+n].to_vec());
+            *i += n;
+            ret
+        };
+        let is_consonant = |x: &VChar| if let VChar::Consonant(_) = *x {true} else {false};
+
+        let n = data.iter().take_while(|x| is_consonant(x)).count();
+        let mut initial = part(&mut i, n);
+        if !initial.is_empty() && !util::allowed_prefix().contains(&initial.to_lowercase()) {
+            return None;
+        }
+        // 'gi' is a consonant before another vovel: "gia", but "gì".
+        if initial.to_lowercase() == VWord::from_str("g")
+        && vovel(data.get(i)) == Some((Raw::I, Flag::N)) && vovel(data.get(i+1)).is_some() {
+            initial.extend(&part(&mut i, 1));
+        }
+
+        let glide = {
+            let is_q = initial.to_lowercase() == VWord::from_str("q");
+            let n = match (vovel(data.get(i)), vovel(data.get(i+1))) {
+                // 'u' always follows 'q': "qua", "quốc", "quý".
+                (Some((Raw::U, Flag::N)), Some(_)) if is_q => 1,
+                _ if is_q => return None,
+                (Some((Raw::O, Flag::N)), Some((Raw::A, _)))
+                | (Some((Raw::O, Flag::N)), Some((Raw::E, Flag::N))) => 1,
+                (Some((Raw::U, Flag::N)), Some((Raw::Y, Flag::N)))
+                | (Some((Raw::U, Flag::N)), Some((Raw::E, Flag::D)))
+                | (Some((Raw::U, Flag::N)), Some((Raw::A, Flag::D)))
+                | (Some((Raw::U, Flag::N)), Some((Raw::O, Flag::W))) => 1,
+                _ => 0,
+            };
+            part(&mut i, n)
+        };
+
+        let nucleus = {
+            let pair = (vovel(data.get(i)), vovel(data.get(i+1)));
+            let n = match pair {
+                (Some(x), Some(y)) => {
+                    if DOUBLE_VOVELS.iter().any(|v| x == (v.0.clone(), v.1.clone())
+                                                && y == (v.2.clone(), v.3.clone())) {
+                        2
+                    } else {
+                        1
+                    }
+                },
+                (Some(_), None) => 1,
+                _ => return None,
+            };
+            part(&mut i, n)
+        };
+
+        let coda = match vovel(data.get(i)) {
+            Some((Raw::I, Flag::N)) | Some((Raw::Y, Flag::N))
+            | Some((Raw::O, Flag::N)) | Some((Raw::U, Flag::N)) => part(&mut i, 1),
+            Some(_) => return None,
+            None => {
+                let n = data.len() - i;
+                let coda = part(&mut i, n);
+                if !coda.is_empty() && !util::allowed_postfix().contains(&coda.to_lowercase()) {
+                    return None;
+                }
+                coda
+            },
+        };
+        if i < data.len() {
+            return None;
+        }
+
+        Some(Syllable {
+            initial: initial,
+            glide: glide,
+            nucleus: nucleus,
+            coda: coda,
+            tone: tone,
+        })
+    }
+
+/// Return the syllable as a word, with the tone placed in the given style.
+    pub fn to_vword(&self, placement: &TonePlacement) -> VWord {
+        let mut ret = VWord::new();
+        ret.extend(&self.initial);
+        ret.extend(&self.glide);
+        ret.extend(&self.nucleus);
+        ret.extend(&self.coda);
+
+        let start = self.initial.len() + self.glide.len();
+        let index = if self.nucleus.len() == 2 {
+            let x = vovel(self.nucleus.vchars().get(0)).unwrap();
+            let y = vovel(self.nucleus.vchars().get(1)).unwrap();
+            let first = DOUBLE_VOVELS.iter().any(|v| v.4 && x == (v.0.clone(), v.1.clone())
+                                                  && y == (v.2.clone(), v.3.clone()));
+            if first { start } else { start + 1 }
+        } else if *placement == TonePlacement::Old && self.coda.is_empty()
+        && !self.glide.is_empty() && self.initial.to_lowercase() != VWord::from_str("q")
+        && match vovel(self.nucleus.vchars().get(0)) {
+            Some((Raw::A, Flag::N)) | Some((Raw::E, Flag::N)) | Some((Raw::Y, Flag::N)) => true,
+            _ => false,
+        } {
+            // The glide of "hoa", "khoe", "thuy" in the old style.
+            start - 1
+        } else {
+            start
+        };
+
+        if self.tone != Tone::N {
+            let (data, upcase): (Vec<_>, Vec<_>) = ret.iter().enumerate()
+                .map(|(i, (x, up))| match *x {
+                    VChar::Vovel(ref raw, ref flag, _) if i == index => {
+                        (VChar::Vovel(raw.clone(), flag.clone(), self.tone.clone()), *up)
+                    },
+                    _ => (x.clone(), *up),
+                }).unzip();
+            ret = VWord::new_raw(data, upcase);
+        }
+        ret
+    }
+}
+
+// Render the syllable with the tone in the old style.
+impl fmt::Display for Syllable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_vword(&TonePlacement::Old))
+    }
+}
+
+#[test]
+fn test_syllable_parse() {
+    fn test(s: &str, parts: [&str; 4], tone: Tone) {
+        let x = Syllable::parse(&VWord::from_str(s)).unwrap();
+        assert_eq!((s, x.initial.to_string(), x.glide.to_string(),
+                    x.nucleus.to_string(), x.coda.to_string(), x.tone),
+                   (s, parts[0].to_owned(), parts[1].to_owned(),
+                    parts[2].to_owned(), parts[3].to_owned(), tone));
+    }
+    test("thuyền", ["th", "u", "yê", "n"], Tone::F);
+    test("gì", ["g", "", "i", ""], Tone::F);
+    test("gìn", ["g", "", "i", "n"], Tone::F);
+    test("giá", ["gi", "", "a", ""], Tone::S);
+    test("giếng", ["gi", "", "ê", "ng"], Tone::S);
+    test("quý", ["q", "u", "y", ""], Tone::S);
+    test("quốc", ["q", "u", "ô", "c"], Tone::S);
+    test("nghiêng", ["ngh", "", "iê", "ng"], Tone::N);
+    test("nguyên", ["ng", "u", "yê", "n"], Tone::N);
+    test("ngoài", ["ng", "o", "a", "i"], Tone::F);
+    test("thuở", ["th", "u", "ơ", ""], Tone::R);
+    test("khuya", ["kh", "u", "ya", ""], Tone::N);
+    test("người", ["ng", "", "ươ", "i"], Tone::F);
+    test("mua", ["m", "", "ua", ""], Tone::N);
+    test("ăn", ["", "", "ă", "n"], Tone::N);
+    test("ĐƯỜNG", ["Đ", "", "ƯƠ", "NG"], Tone::F);
+
+    for s in &["", "str", "hi!", "qa", "qu", "phugn", "hóà", "aoe", "nhanhh"] {
+        assert_eq!((s, Syllable::parse(&VWord::from_str(s))), (s, None));
+    }
+}
+#[test]
+fn test_syllable_to_vword() {
+    fn test(s: &str, new: &str) {
+        let x = Syllable::parse(&VWord::from_str(s)).unwrap();
+        assert_eq!(x.to_string(), s);
+        assert_eq!(x.to_vword(&TonePlacement::New).to_string(), new);
+    }
+    test("thuyền", "thuyền"); test("hòa", "hoà"); test("khỏe", "khoẻ");
+    test("thúy", "thuý"); test("quý", "quý"); test("hoàn", "hoàn");
+    test("giữa", "giữa"); test("của", "của"); test("ĐƯỜNG", "ĐƯỜNG");
+    test("Việt", "Việt"); test("khuỷu", "khuỷu");
+}
@@ -2,30 +2,60 @@
 // Licensed under the MIT license, see the LICENSE file or
 // <http://opensource.org/licenses/MIT>
 
-use vword::{ VWord, VChar };
-use std::collections::HashSet;
+use vword::{ VWord, VChar, Raw, Flag, Tone, TonePlacement };
+use syllable::Syllable;
+use std::borrow::Cow;
+use std::collections::{ BTreeSet, HashSet };
+use std::fmt;
+
+lazy_static! {
+    static ref ALLOWED_PREFIX: HashSet<VWord> = vec![
+        "ph", "th", "tr", "gi", "d", "ch", "nh", "ng", "ngh", "kh", "g", "gh",
+        "c", "q", "k", "t", "r", "h", "b", "m", "v", "đ", "n", "l", "x", "p",
+        "s",
+    ].iter().map(|x| VWord::from_str(x)).collect();
+    static ref ALLOWED_POSTFIX: HashSet<VWord> = vec![
+        "n", "ng", "t", "c", "m", "nh", "ch", "p",
+    ].iter().map(|x| VWord::from_str(x)).collect();
+    // Glide, nucleus and final vovel of the rhymes (vần) without a final
+    // consonant, eg: "oai" in "ngoài".
+    static ref OPEN_RHYMES: HashSet<&'static str> = vec![
+        "a", "e", "ê", "i", "o", "ô", "ơ", "u", "ư", "y",
+        "ai", "ao", "au", "ay", "âu", "ây", "eo", "êu", "ia", "iu", "iêu", "oa",
+        "oe", "oi", "ôi", "ơi", "ua", "uê", "ui", "uơ", "uy", "uya", "uyu", "uây",
+        "uôi", "ưa", "ưi", "ưu", "ươi", "ươu", "yêu", "oai", "oay", "oeo",
+    ].into_iter().collect();
+    // Glide and nucleus of the rhymes with a final consonant, eg: "uyê" in
+    // "nguyên".
+    static ref CLOSED_RHYMES: HashSet<&'static str> = vec![
+        "a", "ă", "â", "e", "ê", "i", "o", "ô", "ơ", "u", "ư",
+        "oa", "oă", "oe", "oo", "uâ", "uê", "uy", "iê", "yê", "uô", "ươ", "uyê",
+    ].into_iter().collect();
+}
+
+/// Return the consonant clusters which can start a word (phụ âm đầu),
+/// in lowercase.
+pub fn allowed_prefix() -> &'static HashSet<VWord> { &ALLOWED_PREFIX }
+/// Return the consonant clusters which can end a word (phụ âm cuối),
+/// in lowercase.
+pub fn allowed_postfix() -> &'static HashSet<VWord> { &ALLOWED_POSTFIX }
 
 pub fn is_vietnamese(input: &VWord) -> bool {
+    check_vietnamese(input).is_ok()
+}
+
+/// Return why `is_vietnamese` rejects `input`, eg: `SpellingError::Initial`
+/// for "string".
+pub fn check_vietnamese(input: &VWord) -> Result<(), SpellingError> {
     let raw: &Vec<VChar> = input.vchars();
 
-    // Return false if input cointains Invalid character
-    for x in raw.iter() {
-        if let VChar::Invalid(_) = *x {
-            return false;
+    // Return an error if input cointains Invalid character
+    for (i, x) in raw.iter().enumerate() {
+        if let VChar::Invalid(c) = *x {
+            return Err(SpellingError::Character(i, c));
         }
     }
 
-    lazy_static! {
-        static ref ALLOWED_PREFIX: HashSet<VWord> = vec![
-            "ph", "th", "tr", "gi", "d", "ch", "nh", "ng", "ngh", "kh", "g", "gh",
-            "c", "q", "k", "t", "r", "h", "b", "m", "v", "đ", "n", "l", "x", "p",
-            "s",
-        ].iter().map(|x| VWord::from_str(x)).collect();
-        static ref ALLOWED_POSTFIX: HashSet<VWord> = vec![
-            "n", "ng", "t", "c", "m", "nh", "ch", "p",
-        ].iter().map(|x| VWord::from_str(x)).collect();
-    }
-
     // Split input into slice of same VChar kind (Consonant or Vovel)
     // Example: 'chich' will be split into ['ch', 'i', 'ch']
     let split: Vec<VWord> = {
@@ -51,7 +81,7 @@ pub fn is_vietnamese(input: &VWord) -> bool {
     };
 
     if split.len() > 3 {
-        return false;
+        return Err(SpellingError::Groups(split.len()));
     }
 
     fn is_consonants(x: &VWord) -> bool {
@@ -63,21 +93,327 @@ pub fn is_vietnamese(input: &VWord) -> bool {
             }
         }
     }
-    
+    let prefix = |x: &VWord| if !is_consonants(x) || ALLOWED_PREFIX.contains(x) {
+        Ok(())
+    } else {
+        Err(SpellingError::Initial(x.to_string()))
+    };
+    let postfix = |x: &VWord| if !is_consonants(x) || ALLOWED_POSTFIX.contains(x) {
+        Ok(())
+    } else {
+        Err(SpellingError::Final(x.to_string()))
+    };
+
     if split.len() == 1 {
-        true
+        Ok(())
     } else if split.len() == 2 {
-        let check_1 = !is_consonants(&split[0]) || ALLOWED_PREFIX.contains(&split[0]);
-        let check_2 = !is_consonants(&split[1]) || ALLOWED_POSTFIX.contains(&split[1]);
-        check_1 && check_2
+        try!(prefix(&split[0]));
+        postfix(&split[1])
     } else if split.len() == 3 {
         if !is_consonants(&split[0]) {
-            false
+            Err(SpellingError::Shape)
         } else {
-            ALLOWED_PREFIX.contains(&split[0]) && ALLOWED_POSTFIX.contains(&split[2])
+            try!(prefix(&split[0]));
+            postfix(&split[2])
         }
     } else { // == 0 ?
-        true
+        Ok(())
+    }
+}
+
+/// How much of the spelling `is_valid` checks.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+#[derive(RustcDecodable, RustcEncodable)]
+pub enum Validation {
+    /// Only the consonant clusters, see `is_vietnamese`.
+    Lenient,
+    /// Every rule, see `validate`.
+    Strict,
+}
+
+/// The rule of the spelling a word breaks, see `validate`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SpellingError {
+    /// A character which is not a Vietnamese letter, and its position.
+    Character(usize, char),
+    /// The letters do not make one syllable, eg: "" or "aba".
+    Shape,
+    /// More groups of consonants and vovels than in a syllable, the most
+    /// being consonants, vovels, consonants. Eg: 4 for "bana".
+    Groups(usize),
+    /// More than one vovel has a tone.
+    Tones,
+    /// An unknown initial consonant cluster, eg: "str".
+    Initial(String),
+    /// An unknown final consonant cluster, eg: "l".
+    Final(String),
+    /// Vovels which do not make a rhyme (vần), eg: "aoe" or "ăi".
+    Rhyme(String),
+    /// An initial consonant spelled wrong before the vovel which follows
+    /// it, eg: "ke" is spelled with 'k' and "ca" with 'c', not "ce", "ka".
+    Spelling(String, String),
+    /// A syllable which ends in 'p', 't', 'c' or 'ch' with another tone
+    /// than sắc or nặng, eg: "càt".
+    StopTone(Tone),
+}
+
+impl fmt::Display for SpellingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SpellingError::Character(i, c) => write!(f, "invalid character `{}` at {}", c, i),
+            SpellingError::Shape => write!(f, "not a syllable"),
+            SpellingError::Groups(n) => write!(f, "{} groups of consonants and vovels", n),
+            SpellingError::Tones => write!(f, "more than one tone"),
+            SpellingError::Initial(ref s) => write!(f, "unknown initial consonant `{}`", s),
+            SpellingError::Final(ref s) => write!(f, "unknown final consonant `{}`", s),
+            SpellingError::Rhyme(ref s) => write!(f, "unknown rhyme `{}`", s),
+            SpellingError::Spelling(ref x, ref y) => write!(f, "`{}` is not written before `{}`", x, y),
+            SpellingError::StopTone(ref t) => {
+                write!(f, "tone {:?} on a syllable ending in p, t, c or ch", t)
+            },
+        }
+    }
+}
+
+/// Return `true` if `input` passes the checks of `validation`.
+pub fn is_valid(input: &VWord, validation: &Validation) -> bool {
+    match *validation {
+        Validation::Lenient => is_vietnamese(input),
+        Validation::Strict => validate(input).is_ok(),
+    }
+}
+
+/// Check every spelling rule on `input` and return its parts.
+/// Unlike `is_vietnamese`, which only checks the consonant clusters, this
+/// checks the rhyme and the final consonants it takes (see `is_rhyme`), the
+/// use of c/k/q, g/gh and ng/ngh before the vovel, and that syllables ending
+/// in 'p', 't', 'c', 'ch' only take sắc or nặng.
+pub fn validate(input: &VWord) -> Result<Syllable, SpellingError> {
+    let word = input.to_lowercase();
+    let mut tones = 0;
+    for (i, x) in word.vchars().iter().enumerate() {
+        match *x {
+            VChar::Invalid(c) => return Err(SpellingError::Character(i, c)),
+            VChar::Vovel(_, _, ref t) if *t != Tone::N => tones += 1,
+            _ => {},
+        }
+    }
+    if tones > 1 {
+        return Err(SpellingError::Tones);
+    }
+
+    let syllable = match Syllable::parse(&word) {
+        Some(x) => x,
+        None => return Err(shape_error(&word)),
+    };
+
+    let rhyme = {
+        let mut x = syllable.glide.clone();
+        x.extend(&syllable.nucleus);
+        if let Some(&VChar::Vovel(_, _, _)) = syllable.coda.vchars().first() {
+            x.extend(&syllable.coda);
+        }
+        x.to_string()
+    };
+    let closed = match syllable.coda.vchars().first() {
+        Some(&VChar::Consonant(_)) => true,
+        _ => false,
+    };
+    if !is_rhyme(&syllable, &rhyme, closed) {
+        let coda = if closed { syllable.coda.to_string() } else { String::new() };
+        return Err(SpellingError::Rhyme(rhyme + &coda));
+    }
+    let initial = syllable.initial.to_string();
+    let coda = syllable.coda.to_string();
+
+    let first = syllable.glide.vchars().first().or(syllable.nucleus.vchars().first());
+    let (front_i, front_y) = match first {
+        Some(&VChar::Vovel(Raw::I, Flag::N, _)) => (true, false),
+        Some(&VChar::Vovel(Raw::E, _, _)) => (true, false),
+        Some(&VChar::Vovel(Raw::Y, Flag::N, _)) => (false, true),
+        _ => (false, false),
+    };
+    let ok = match initial.as_str() {
+        "c" | "ng" => !front_i && !front_y,
+        "k" => front_i || front_y,
+        "gh" | "ngh" => front_i,
+        // "gì", but "ghe"
+        "g" => !front_i || first == Some(&VChar::Vovel(Raw::I, Flag::N, Tone::N)),
+        _ => true,
+    };
+    if !ok {
+        return Err(SpellingError::Spelling(initial, rhyme));
+    }
+
+    if ["p", "t", "c", "ch"].contains(&coda.as_str())
+    && syllable.tone != Tone::N && syllable.tone != Tone::S && syllable.tone != Tone::J {
+        return Err(SpellingError::StopTone(syllable.tone.clone()));
+    }
+    Ok(syllable)
+}
+
+// Return `true` if the glide, nucleus and final vovel of `syllable` (its
+// `rhyme`) make a known rhyme, followed by a final consonant if `closed`
+// which may end it.
+fn is_rhyme(syllable: &Syllable, rhyme: &str, closed: bool) -> bool {
+    let initial = syllable.initial.to_string();
+    let rhymes = if closed { &*CLOSED_RHYMES } else { &*OPEN_RHYMES };
+    // The 'u' of 'qu' may be followed by a rhyme without glide: "quan", "quai".
+    let known = rhymes.contains(rhyme) || (initial == "q" && {
+        let rest = &rhyme["u".len()..];
+        rhymes.contains(rest) && !rest.starts_with('o') && !rest.starts_with('u')
+    });
+    let front = match syllable.nucleus.vchars().last() {
+        Some(&VChar::Vovel(Raw::A, Flag::N, _)) | Some(&VChar::Vovel(Raw::E, Flag::D, _))
+        | Some(&VChar::Vovel(Raw::I, _, _)) | Some(&VChar::Vovel(Raw::Y, _, _)) => true,
+        _ => false,
+    };
+    let coda = syllable.coda.to_string();
+    let nucleus = syllable.nucleus.to_string();
+    known
+    // 'ch' and 'nh' only follow a single front vovel: "cách", "xinh", "huỳnh".
+    && !((coda == "ch" || coda == "nh") && (!front || nucleus.chars().count() > 1))
+    // 'yê' starts a syllable or follows a glide: "yên", "chuyện", but "tiên".
+    && !(nucleus == "yê" && !initial.is_empty() && syllable.glide.is_empty())
+    // 'oo' is only followed by 'c' or 'ng': "xoong", "moóc".
+    && !(nucleus == "oo" && coda != "ng" && coda != "c")
+}
+
+// Return why `word`, in lowercase and with at most one tone, is not shaped
+// like a syllable.
+fn shape_error(word: &VWord) -> SpellingError {
+    let is_vovel = |x: &VChar| if let VChar::Vovel(_, _, _) = *x {true} else {false};
+    let data = word.vchars();
+    let start = data.iter().position(|x| is_vovel(x));
+    let end = data.iter().rposition(|x| is_vovel(x));
+    let (start, end) = match (start, end) {
+        (Some(x), Some(y)) => (x, y + 1),
+        _ => return SpellingError::Shape,
+    };
+    let to_vword = |x: &[VChar]| VWord::new_raw(x.to_vec(), vec![false; x.len()]);
+    let initial = to_vword(&data[..start]);
+    let coda = to_vword(&data[end..]);
+    let mut rhyme = to_vword(&data[start..end]);
+    rhyme.remove_tone();
+    if !initial.is_empty() && !ALLOWED_PREFIX.contains(&initial) {
+        SpellingError::Initial(initial.to_string())
+    } else if !coda.is_empty() && !ALLOWED_POSTFIX.contains(&coda) {
+        SpellingError::Final(coda.to_string())
+    } else if !data[start..end].iter().all(|x| is_vovel(x)) {
+        SpellingError::Shape
+    } else if initial == VWord::from_str("q") {
+        SpellingError::Spelling(initial.to_string(), rhyme.to_string())
+    } else {
+        SpellingError::Rhyme(rhyme.to_string())
+    }
+}
+
+/// Return every syllable which passes `validate`, with every tone, in
+/// lowercase and with the tone in the old style. Eg: "a", "á", ..., "nghiêng".
+pub fn syllables() -> Vec<VWord> {
+    let mut initials: Vec<String> = ALLOWED_PREFIX.iter().map(|x| x.to_string()).collect();
+    initials.push(String::new());
+    initials.push("qu".to_owned());
+    let mut rhymes: Vec<String> = OPEN_RHYMES.iter().map(|x| x.to_string()).collect();
+    for x in CLOSED_RHYMES.iter() {
+        for y in ALLOWED_POSTFIX.iter() {
+            rhymes.push(x.to_string() + &y.to_string());
+        }
+    }
+
+    let mut ret = BTreeSet::new();
+    for initial in &initials {
+        for rhyme in &rhymes {
+            // The 'i' of 'gi' is dropped before another 'i': "gìn", "giếng".
+            if initial == "gi" && rhyme.starts_with('i') {
+                continue;
+            }
+            let mut syllable = match validate(&VWord::from_str(&(initial.clone() + rhyme))) {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            for tone in &[Tone::N, Tone::S, Tone::F, Tone::R, Tone::X, Tone::J] {
+                syllable.tone = tone.clone();
+                let word = syllable.to_vword(&TonePlacement::Old);
+                if validate(&word).is_ok() {
+                    ret.insert(word);
+                }
+            }
+        }
+    }
+    ret.into_iter().collect()
+}
+
+/// Move the tone of every Vietnamese word of `text` to where `placement`
+/// puts it. Eg: "Hòa thuận" in the new style is "Hoà thuận".
+pub fn convert_tone_placement(text: &str, placement: &TonePlacement) -> String {
+    let mut ret = String::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, ret: &mut String| {
+        let mut vword = VWord::from_str(word);
+        if is_vietnamese(&vword) {
+            vword.replace_tone(Some(placement));
+            ret.push_str(&vword.to_string());
+        } else {
+            ret.push_str(word);
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_alphabetic() {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut ret);
+            ret.push(c);
+        }
+    }
+    flush(&mut word, &mut ret);
+    ret
+}
+
+/// The diacritics `remove_diacritics` removes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Diacritics {
+    /// The tones: "Việt" gives "Viêt".
+    Tones,
+    /// The flags of the vovels and the bar of 'đ': "Đường" gives "Dường".
+    Marks,
+    /// Both: "Đường" gives "Duong".
+    All,
+}
+
+// Return `c` without the diacritics `which`, or `None` if it is the same.
+fn strip(c: char, which: &Diacritics) -> Option<char> {
+    let tones = *which != Diacritics::Marks;
+    let marks = *which != Diacritics::Tones;
+    let (x, upcase) = VChar::from_char(c);
+    let x = match x {
+        VChar::Vovel(ref raw, ref flag, ref tone) => {
+            let flag = if marks { Flag::N } else { flag.clone() };
+            let tone = if tones { Tone::N } else { tone.clone() };
+            VChar::Vovel(raw.clone(), flag, tone).to_char()
+        },
+        VChar::Consonant('đ') if marks => 'd',
+        _ => return None,
+    };
+    let x = if upcase { x.to_uppercase().next().unwrap_or(x) } else { x };
+    if x == c { None } else { Some(x) }
+}
+
+/// Remove the diacritics `which` from the Vietnamese letters of `text`,
+/// keeping their case, eg: "Tiếng Việt" gives "Tieng Viet".
+/// Other characters are left as is, and `text` is not copied if nothing
+/// changes.
+pub fn remove_diacritics<'a>(text: &'a str, which: &Diacritics) -> Cow<'a, str> {
+    let first = text.char_indices().filter_map(|(i, c)| strip(c, which).map(|_| i)).next();
+    match first {
+        None => Cow::Borrowed(text),
+        Some(i) => {
+            let mut ret = String::with_capacity(text.len());
+            ret.push_str(&text[..i]);
+            ret.extend(text[i..].chars().map(|c| strip(c, which).unwrap_or(c)));
+            Cow::Owned(ret)
+        },
     }
 }
 
@@ -93,4 +429,93 @@ fn test_is_vietnamese() {
     test("phugn", false);
 
     test("giao", true);
+    test("vie\u{302}\u{323}t", true); test("vie\u{302}\u{323}\u{323}t", false);
+}
+#[test]
+fn test_check_vietnamese() {
+    fn test(x: &str, res: Result<(), SpellingError>) {
+        let tmp = VWord::from_str(x);
+        assert_eq!((x, check_vietnamese(&tmp)), (x, res));
+    }
+    test("hi!", Err(SpellingError::Character(2, '!')));
+    test("string", Err(SpellingError::Initial("str".to_owned())));
+    test("Stop", Err(SpellingError::Initial("st".to_owned())));
+    test("hal", Err(SpellingError::Final("l".to_owned())));
+    test("ăl", Err(SpellingError::Final("l".to_owned())));
+    test("banana", Err(SpellingError::Groups(6)));
+    test("aba", Err(SpellingError::Shape));
+    test("nghiêng", Ok(())); test("a", Ok(())); test("", Ok(()));
+}
+#[test]
+fn test_validate() {
+    fn test(x: &str, res: Option<SpellingError>) {
+        let tmp = VWord::from_str(x);
+        assert_eq!((x, validate(&tmp).err()), (x, res));
+    }
+    for x in &["thuyền", "Việt", "gì", "giếng", "ghế", "nghiêng", "kỹ", "quý", "quân",
+               "quốc", "khuya", "khuỷu", "ngoài", "người", "thuở", "hoặc", "xoong",
+               "các", "cạnh", "yên", "ăn", "ĐƯỜNG", "huỳnh", "giữa", "chuyện",
+               "moóc", "xinh", "bệnh", "khoanh"] {
+        test(x, None);
+    }
+    test("ká", Some(SpellingError::Spelling("k".to_owned(), "a".to_owned())));
+    test("gé", Some(SpellingError::Spelling("g".to_owned(), "e".to_owned())));
+    test("ngi", Some(SpellingError::Spelling("ng".to_owned(), "i".to_owned())));
+    test("ci", Some(SpellingError::Spelling("c".to_owned(), "i".to_owned())));
+    test("gha", Some(SpellingError::Spelling("gh".to_owned(), "a".to_owned())));
+    test("qa", Some(SpellingError::Spelling("q".to_owned(), "a".to_owned())));
+    test("càt", Some(SpellingError::StopTone(Tone::F)));
+    test("hỏch", Some(SpellingError::Rhyme("och".to_owned())));
+    test("hỉch", Some(SpellingError::StopTone(Tone::R)));
+    test("aoe", Some(SpellingError::Rhyme("aoe".to_owned())));
+    test("ăi", Some(SpellingError::Rhyme("ăi".to_owned())));
+    test("tiê", Some(SpellingError::Rhyme("iê".to_owned())));
+    test("mian", Some(SpellingError::Rhyme("ian".to_owned())));
+    test("bọch", Some(SpellingError::Rhyme("och".to_owned())));
+    test("ym", Some(SpellingError::Rhyme("ym".to_owned())));
+    test("lưnh", Some(SpellingError::Rhyme("ưnh".to_owned())));
+    test("tyên", Some(SpellingError::Rhyme("yên".to_owned())));
+    test("boon", Some(SpellingError::Rhyme("oon".to_owned())));
+    test("str", Some(SpellingError::Shape));
+    test("stra", Some(SpellingError::Initial("str".to_owned())));
+    test("hal", Some(SpellingError::Final("l".to_owned())));
+    test("bana", Some(SpellingError::Shape));
+    test("hóà", Some(SpellingError::Tones));
+    test("hi!", Some(SpellingError::Character(2, '!')));
+
+    // The lenient check lets these through.
+    assert!(is_valid(&VWord::from_str("ká"), &Validation::Lenient));
+    assert!(!is_valid(&VWord::from_str("ká"), &Validation::Strict));
+}
+#[test]
+fn test_remove_diacritics() {
+    fn test(text: &str, which: Diacritics, s: &str) {
+        assert_eq!((text, &*remove_diacritics(text, &which)), (text, s));
+    }
+    test("Tiếng Việt", Diacritics::All, "Tieng Viet");
+    test("Tiếng Việt", Diacritics::Tones, "Tiêng Viêt");
+    test("Tiếng Việt", Diacritics::Marks, "Tiéng Viẹt");
+    test("ĐƯỜNG đi", Diacritics::All, "DUONG di");
+    test("ĐƯỜNG đi", Diacritics::Tones, "ĐƯƠNG đi");
+    test("ĐƯỜNG đi", Diacritics::Marks, "DUÒNG di");
+    test("Ça, naïve ß Ω 123!", Diacritics::All, "Ça, naïve ß Ω 123!");
+    test("", Diacritics::All, "");
+
+    match remove_diacritics("Tieng Viet", &Diacritics::All) {
+        Cow::Borrowed(x) => assert_eq!(x, "Tieng Viet"),
+        Cow::Owned(_) => panic!("copied"),
+    }
+    match remove_diacritics("Tiêng", &Diacritics::Tones) {
+        Cow::Borrowed(_) => {},
+        Cow::Owned(_) => panic!("copied"),
+    }
+}
+#[test]
+fn test_convert_tone_placement() {
+    let old = "Hòa bình, thúy, khỏe, HÒA, hoàn toàn, ngoài, thuở, bảo.";
+    let new = "Hoà bình, thuý, khoẻ, HOÀ, hoàn toàn, ngoài, thuở, bảo.";
+    assert_eq!(convert_tone_placement(old, &TonePlacement::New), new);
+    assert_eq!(convert_tone_placement(new, &TonePlacement::Old), old);
+    assert_eq!(convert_tone_placement("hòa", &TonePlacement::Old), "hòa");
+    assert_eq!(convert_tone_placement("", &TonePlacement::New), "");
 }
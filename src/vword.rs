@@ -35,6 +35,7 @@ pub enum VChar {
     Invalid(char),
 }
 #[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+#[derive(RustcDecodable, RustcEncodable)]
 pub enum Tone {
     N, // None
     S, // /
@@ -44,14 +45,27 @@ pub enum Tone {
     J, // .
 }
 #[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+#[derive(RustcDecodable, RustcEncodable)]
 pub enum Raw {
     A, E, I, O, U, Y,
 }
+/// Where to put the tone of 'oa', 'oe' and 'uy' which end a syllable.
+/// `TonePlacement::Old`: hòa, khỏe, thúy.
+/// `TonePlacement::New`: hoà, khoẻ, thuý.
+/// Without a style, `VWord::toggle_tone` gives hoà, khoẻ, thúy.
+/// Both put it on the second vovel when a consonant follows: hoàn, huỳnh.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+#[derive(RustcDecodable, RustcEncodable)]
+pub enum TonePlacement {
+    Old,
+    New,
+}
 /// Enum to hold 'flag' of vovel.
 /// `Flag::N`: Flag for a, e, i,... (No flag)
 /// `Flag::W`: Flag for ă, ư, ơ
 /// `Flag::D`: Flag for â, ô, ê
 #[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+#[derive(RustcDecodable, RustcEncodable)]
 pub enum Flag {
     N, // Flag for a, e, i, o, y, u
     W, // Flag for ă, ơ,...
@@ -112,6 +126,83 @@ fn vovel_table_reverse() -> &'static BTreeMap<char, (Raw, Flag, Tone)> {
     &X
 }
 
+// Return the VIQR mark which puts `flag` on `raw`, eg: '^' for 'â'.
+fn viqr_flag(raw: &Raw, flag: &Flag) -> Option<char> {
+    match (raw, flag) {
+        (&Raw::A, &Flag::D) | (&Raw::E, &Flag::D) | (&Raw::O, &Flag::D) => Some('^'),
+        (&Raw::A, &Flag::W) => Some('('),
+        (&Raw::O, &Flag::W) | (&Raw::U, &Flag::W) => Some('+'),
+        _ => None,
+    }
+}
+fn viqr_tone(tone: &Tone) -> Option<char> {
+    match *tone {
+        Tone::N => None,
+        Tone::S => Some('\''),
+        Tone::F => Some('`'),
+        Tone::R => Some('?'),
+        Tone::X => Some('~'),
+        Tone::J => Some('.'),
+    }
+}
+fn is_viqr_mark(c: char) -> bool {
+    match c {
+        '^' | '(' | '+' | '\'' | '`' | '?' | '~' | '.' => true,
+        _ => false,
+    }
+}
+
+// Return the flag a combining mark puts on `raw`, eg: `Flag::D` for
+// U+0302 (circumflex) on 'a', 'e', 'o'.
+fn combining_flag(raw: &Raw, c: char) -> Option<Flag> {
+    match (raw, c) {
+        (&Raw::A, '\u{302}') | (&Raw::E, '\u{302}') | (&Raw::O, '\u{302}') => Some(Flag::D),
+        (&Raw::A, '\u{306}') | (&Raw::O, '\u{31B}') | (&Raw::U, '\u{31B}') => Some(Flag::W),
+        _ => None,
+    }
+}
+fn combining_tone(c: char) -> Option<Tone> {
+    match c {
+        '\u{301}' => Some(Tone::S),
+        '\u{300}' => Some(Tone::F),
+        '\u{309}' => Some(Tone::R),
+        '\u{303}' => Some(Tone::X),
+        '\u{323}' => Some(Tone::J),
+        _ => None,
+    }
+}
+// Return the combining mark of `flag` on `raw`, and its combining class.
+fn flag_mark(raw: &Raw, flag: &Flag) -> Option<(char, u8)> {
+    match (raw, flag) {
+        (_, &Flag::D) => Some(('\u{302}', 230)),
+        (&Raw::A, &Flag::W) => Some(('\u{306}', 230)),
+        (_, &Flag::W) => Some(('\u{31B}', 216)),
+        _ => None,
+    }
+}
+fn tone_mark(tone: &Tone) -> Option<(char, u8)> {
+    match *tone {
+        Tone::N => None,
+        Tone::S => Some(('\u{301}', 230)),
+        Tone::F => Some(('\u{300}', 230)),
+        Tone::R => Some(('\u{309}', 230)),
+        Tone::X => Some(('\u{303}', 230)),
+        Tone::J => Some(('\u{323}', 220)),
+    }
+}
+
+/// How `VWord::render` writes a vovel.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+pub enum Normalization {
+    /// Precomposed, eg: "ế" is U+1EBF. This is what `to_string` writes.
+    Nfc,
+    /// Decomposed, eg: "ế" is "e" U+0302 U+0301.
+    Nfd,
+    /// The vovel with its flag precomposed and the tone as a combining mark,
+    /// as Windows-1258 writes it, eg: "ế" is "ê" U+0301.
+    Windows1258,
+}
+
 // Implicitly implement `ToString` for `VChar`
 // Write a space for invalid Vietnamese vovels. Eg: `Vovel(I, W, N)`
 impl fmt::Display for VChar {
@@ -206,16 +297,157 @@ impl VWord {
         self.data.push(c);
         self.upcase.push(is_uppercase);
     }
+/// Remove the last VChar and return it with its case.
+    pub fn pop(&mut self) -> Option<(VChar, bool)> {
+        match (self.data.pop(), self.upcase.pop()) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        }
+    }
+/// Append every VChar of `other`.
+    pub fn extend(&mut self, other: &VWord) {
+        self.data.extend(other.data.iter().cloned());
+        self.upcase.extend(other.upcase.iter().cloned());
+    }
+/// Return a copy of the word in lowercase.
+    pub fn to_lowercase(&self) -> VWord {
+        VWord::new_raw(self.data.clone(), vec![false; self.data.len()])
+    }
+    pub fn len(&self) -> usize { self.data.len() }
+    pub fn is_empty(&self) -> bool { self.data.is_empty() }
 /// Return a `VWord` from a string.
+/// The combining marks of tones and flags join the vovel before them, so
+/// decomposed text (NFD) is read like precomposed text.
     pub fn from_str(s: &str) -> VWord {
         // Return value
         let mut ret = VWord::new();
         for c in s.chars() {
+            if let Some(&mut VChar::Vovel(ref raw, ref mut flag, ref mut tone)) = ret.data.last_mut() {
+                match (combining_flag(raw, c), combining_tone(c)) {
+                    (Some(x), _) if *flag == Flag::N => {
+                        *flag = x;
+                        continue;
+                    },
+                    (_, Some(x)) if *tone == Tone::N => {
+                        *tone = x;
+                        continue;
+                    },
+                    _ => {},
+                }
+            }
             let (x, y) = VChar::from_char(c);
             ret.push(x, y);
         }
         ret
     }
+/// Write the word with its vovels in the given form.
+    pub fn render(&self, form: &Normalization) -> String {
+        let mut ret = String::new();
+        for (x, up) in self.iter() {
+            let case = |c: char| if *up { c.to_uppercase().next().unwrap_or(c) } else { c };
+            match (x, form) {
+                (&VChar::Vovel(ref raw, ref flag, ref tone), &Normalization::Nfd) => {
+                    ret.push(case(VChar::Vovel(raw.clone(), Flag::N, Tone::N).to_char()));
+                    let mut marks: Vec<(char, u8)> = flag_mark(raw, flag).into_iter()
+                        .chain(tone_mark(tone)).collect();
+                    // The canonical order of marks, by combining class.
+                    marks.sort_by_key(|x| x.1);
+                    ret.extend(marks.into_iter().map(|x| x.0));
+                },
+                (&VChar::Vovel(ref raw, ref flag, ref tone), &Normalization::Windows1258) => {
+                    ret.push(case(VChar::Vovel(raw.clone(), flag.clone(), Tone::N).to_char()));
+                    ret.extend(tone_mark(tone).map(|x| x.0));
+                },
+                _ => ret.push(case(x.to_char())),
+            }
+        }
+        ret
+    }
+/// Return a `VWord` from a VIQR string, eg: "Vie^.t" for "Việt".
+/// A mark which does not follow a vovel is kept as is, a backslash
+/// makes the next character literal.
+    pub fn from_viqr(s: &str) -> VWord {
+        let mut ret = VWord::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                if let Some(c) = chars.next() {
+                    let (x, y) = VChar::from_char(c);
+                    ret.push(x, y);
+                }
+                continue;
+            }
+            let (mut x, y) = VChar::from_char(c);
+            if x == VChar::Consonant('d')
+            && chars.peek().map_or(false, |c| *c == 'd' || *c == 'D') {
+                chars.next();
+                x = VChar::Consonant('đ');
+            }
+            if let VChar::Vovel(ref raw, ref mut flag, ref mut tone) = x {
+                for f in &[Flag::D, Flag::W] {
+                    if chars.peek().is_some() && chars.peek() == viqr_flag(raw, f).as_ref() {
+                        chars.next();
+                        *flag = f.clone();
+                        break;
+                    }
+                }
+                for t in &[Tone::S, Tone::F, Tone::R, Tone::X, Tone::J] {
+                    if chars.peek().is_some() && chars.peek() == viqr_tone(t).as_ref() {
+                        chars.next();
+                        *tone = t.clone();
+                        break;
+                    }
+                }
+            }
+            ret.push(x, y);
+        }
+        ret
+    }
+/// Render the word as VIQR text, eg: "Vie^.t" for "Việt".
+/// Literal marks which would be read as a modifier are escaped.
+    pub fn to_viqr(&self) -> String {
+        let mut ret = String::new();
+        let mut last: Option<&VChar> = None;
+        for (x, up) in self.iter() {
+            let case = |c: char| {
+                if *up { c.to_uppercase().next().unwrap_or(c) } else { c }
+            };
+            match *x {
+                VChar::Vovel(ref raw, ref flag, ref tone) => {
+                    let base = vovel_table().get(&(raw.clone(), Flag::N, Tone::N));
+                    ret.push(case(*base.unwrap()));
+                    if let Some(c) = viqr_flag(raw, flag) {
+                        ret.push(c);
+                    }
+                    if let Some(c) = viqr_tone(tone) {
+                        ret.push(c);
+                    }
+                },
+                VChar::Consonant('đ') => {
+                    ret.push(case('d'));
+                    ret.push(case('d'));
+                },
+                VChar::Consonant(c) => {
+                    if c == 'd' && last == Some(&VChar::Consonant('d')) {
+                        ret.push('\\');
+                    }
+                    ret.push(case(c));
+                },
+                VChar::Invalid(c) => {
+                    let after_vovel = match last {
+                        Some(&VChar::Vovel(_, _, _)) => true,
+                        _ => false,
+                    };
+                    if c == '\\' || (after_vovel && is_viqr_mark(c)) {
+                        ret.push('\\');
+                    }
+                    ret.push(case(c));
+                },
+            }
+            last = Some(x);
+        }
+        ret
+    }
     pub fn new_raw(data: Vec<VChar>, upcase: Vec<bool>) -> VWord {
         VWord {
             data: data,
@@ -224,6 +456,13 @@ impl VWord {
     }
 /// Set the tone of word
     pub fn toggle_tone(&mut self, tone: &Tone) -> VResult {
+        self.place_tone(tone, None)
+    }
+/// Set the tone of word, in the given style (see `TonePlacement`).
+    pub fn toggle_tone_with(&mut self, tone: &Tone, placement: &TonePlacement) -> VResult {
+        self.place_tone(tone, Some(placement))
+    }
+    fn place_tone(&mut self, tone: &Tone, placement: Option<&TonePlacement>) -> VResult {
         let mut vovels_index: Vec<usize> = Vec::new();
         let mut vovels: Vec<(Raw, Flag)> = Vec::new();
         // Get list of vovels and its index
@@ -304,6 +543,27 @@ impl VWord {
             two_vovels!(U, N, A, N, 0); // ua => ủa
             two_vovels!(U, W, O, W, 1); // ươ => ướ
             two_vovels!(U, W, A, N, 0); // ưa => ừa
+            two_vovels!(O, N, O, N, 1); // oo => oó, "xoóng" in both styles
+        }
+
+        // 'o' of 'oa', 'oe' and 'u' of 'uy' are "âm đệm", which takes the
+        // tone only in the old style, when the vovels end the word. Without
+        // a style, only the 'u' of 'uy' takes it.
+        let closed = match self.data.get(vovels_index[vovels.len()-1] + 1) {
+            Some(&VChar::Consonant(_)) => true,
+            _ => false,
+        };
+        for i in 0..vovels.len()-1 {
+            if (vovels[i] == (Raw::O, Flag::N)
+                && (vovels[i+1] == (Raw::A, Flag::N) || vovels[i+1] == (Raw::E, Flag::N)))
+            || (vovels[i] == (Raw::U, Flag::N) && vovels[i+1] == (Raw::Y, Flag::N)) {
+                let old = match placement {
+                    Some(x) => *x == TonePlacement::Old,
+                    None => vovels[i] == (Raw::U, Flag::N),
+                };
+                let j = if i + 2 == vovels.len() && !closed && old { i } else { i + 1 };
+                return self.data[vovels_index[j]].toggle_tone(&tone);
+            }
         }
 
         // 'y', 'i' is a "phụ âm cuối".
@@ -342,12 +602,100 @@ impl VWord {
         }
         VResult::None
     }
+/// Return the tone of the word, ie: the tone of its last toned vovel.
+    pub fn tone(&self) -> Tone {
+        for x in self.data.iter().rev() {
+            if let VChar::Vovel(_, _, ref tone) = *x {
+                if *tone != Tone::N {
+                    return tone.clone();
+                }
+            }
+        }
+        Tone::N
+    }
+/// Put the tone of the word where `toggle_tone` would put it now, as the
+/// vovels may have changed since the tone was typed. Eg: "hóan" to "hoán".
+/// With a style, the tone is placed as `toggle_tone_with` would.
+    pub fn replace_tone(&mut self, placement: Option<&TonePlacement>) {
+        let tone = self.tone();
+        if tone != Tone::N {
+            self.remove_tone();
+            self.place_tone(&tone, placement);
+        }
+    }
+/// Turn 'uơ' to 'ươ' when something follows it, eg: "thuơng" to "thương".
+/// Return `VResult::None` if there is nothing to fix.
+    pub fn fix_uo(&mut self) -> VResult {
+        match self.find_uo() {
+            Some(i) if i + 2 < self.data.len() => {
+                let is_uo = match (&self.data[i], &self.data[i+1]) {
+                    (&VChar::Vovel(_, Flag::N, _), &VChar::Vovel(_, Flag::W, _)) => true,
+                    _ => false,
+                };
+                // The cluster is followed by something, `toggle_uo` puts
+                // the horn on both vovels.
+                if is_uo { self.toggle_uo(i) } else { VResult::None }
+            },
+            _ => VResult::None,
+        }
+    }
+/// Remove the tone of every vovel.
+/// Return `VResult::None` if there is no tone to remove.
+    pub fn remove_tone(&mut self) -> VResult {
+        let mut ret = VResult::None;
+        for x in &mut self.data {
+            if let VChar::Vovel(_, _, ref mut tone) = *x {
+                if *tone != Tone::N {
+                    *tone = Tone::N;
+                    ret = VResult::Set;
+                }
+            }
+        }
+        ret
+    }
+/// Remove every tone and every flag, and turn 'đ' to 'd'.
+/// Return `VResult::None` if there is nothing to remove.
+    pub fn remove_diacritics(&mut self) -> VResult {
+        let mut ret = self.remove_tone();
+        for x in &mut self.data {
+            match *x {
+                VChar::Vovel(_, ref mut flag, _) => {
+                    if *flag != Flag::N {
+                        *flag = Flag::N;
+                        ret = VResult::Set;
+                    }
+                },
+                VChar::Consonant(ref mut c) => {
+                    if *c == 'đ' {
+                        *c = 'd';
+                        ret = VResult::Set;
+                    }
+                },
+                _ => {},
+            }
+        }
+        ret
+    }
 /// Toggle a specific flag of a specific raw vovel.
 /// Example, toggle flag `Flag::D` of `Raw::A` will turn:
 /// - 'a' to 'â', 'ă' to 'â', return `Ok(())`
 /// - 'â' to 'a', return `Err(())`
     pub fn toggle_vovel(&mut self, raw: &Raw, flag: &Flag) -> VResult {
+        if *flag == Flag::W && (*raw == Raw::U || *raw == Raw::O) {
+            if let Some(i) = self.find_uo() {
+                return self.toggle_uo(i);
+            }
+        }
         for i in (0..self.data.len()).rev() {
+            // 'o' of 'oa', 'oe' is a glide which takes no flag (hoặc, khoe)
+            if *raw == Raw::O {
+                match self.data.get(i+1) {
+                    Some(&VChar::Vovel(Raw::A, _, _)) | Some(&VChar::Vovel(Raw::E, Flag::N, _)) => {
+                        continue;
+                    },
+                    _ => {},
+                }
+            }
             if let VChar::Vovel(ref x, ref mut y, _) = self.data[i] {
                 if x == raw {
                     if y == flag {
@@ -363,12 +711,181 @@ impl VWord {
 
         VResult::None
     }
+// Return the index of 'u' in the last 'uo' cluster (uo, uơ, ưo, ươ, uô).
+// The 'u' of 'qu' is not part of a cluster.
+    fn find_uo(&self) -> Option<usize> {
+        for i in (0..self.data.len()).rev() {
+            match (&self.data[i], self.data.get(i+1)) {
+                (&VChar::Vovel(Raw::U, Flag::N, _), Some(&VChar::Vovel(Raw::O, _, _)))
+                | (&VChar::Vovel(Raw::U, Flag::W, _), Some(&VChar::Vovel(Raw::O, _, _))) => {
+                    if i > 0 && self.data[i-1] == VChar::Consonant('q') {
+                        return None;
+                    }
+                    return Some(i);
+                },
+                (&VChar::Vovel(_, _, _), _) => {},
+                _ => if i + 1 < self.data.len() {
+                    if let VChar::Vovel(_, _, _) = self.data[i+1] {
+                        return None;
+                    }
+                },
+            }
+        }
+        None
+    }
+// Put the horn on the 'uo' cluster starting at `i`. The horn goes on both
+// vovels, except for 'uơ' which ends a word (thuở, huơ).
+    fn toggle_uo(&mut self, i: usize) -> VResult {
+        let u_w = if let VChar::Vovel(_, Flag::W, _) = self.data[i] {true} else {false};
+        let o_w = if let VChar::Vovel(_, Flag::W, _) = self.data[i+1] {true} else {false};
+        let closed = i + 2 < self.data.len();
+        let (u, o, ret) = if u_w && o_w {
+            (Flag::N, Flag::N, VResult::Unset)
+        } else if closed || u_w {
+            (Flag::W, Flag::W, VResult::Set)
+        } else if o_w {
+            (Flag::N, Flag::N, VResult::Unset)
+        } else {
+            (Flag::N, Flag::W, VResult::Set)
+        };
+        if let VChar::Vovel(_, ref mut x, _) = self.data[i] {
+            *x = u;
+        }
+        if let VChar::Vovel(_, ref mut x, _) = self.data[i+1] {
+            *x = o;
+        }
+        ret
+    }
     pub fn iter(&self) -> iter::Zip<slice::Iter<VChar>, slice::Iter<bool>> {
         self.data.iter().zip(self.upcase.iter())
     }
     pub fn vchars(&self) -> &Vec<VChar> { &self.data }
 }
 
+/// What `Matcher` ignores when the query has no diacritic, each level
+/// ignores what the previous one does. Case is always ignored.
+#[derive(Ord, Eq, PartialEq, PartialOrd, Clone, Debug, Hash)]
+pub enum MatchLevel {
+    Exact,
+    /// "tieng" matches "tiếng".
+    IgnoreTone,
+    /// "tieng" matches "tiêng" and "tiếng".
+    IgnoreMarks,
+    /// "duong" matches "đường".
+    IgnoreD,
+}
+
+impl VChar {
+/// Return `true` if `self`, from a query, matches `other`, from a text.
+/// A diacritic of the query must be in the text, but not the other way:
+/// with `MatchLevel::IgnoreTone`, 'e' matches 'é' but 'é' does not match 'e'.
+    pub fn matches(&self, other: &VChar, level: &MatchLevel) -> bool {
+        match (self, other) {
+            (&VChar::Vovel(ref x, ref y, ref z), &VChar::Vovel(ref a, ref b, ref c)) => {
+                x == a
+                && (y == b || (*y == Flag::N && *level >= MatchLevel::IgnoreMarks))
+                && (z == c || (*z == Tone::N && *level >= MatchLevel::IgnoreTone))
+            },
+            (&VChar::Consonant('d'), &VChar::Consonant('đ')) => *level >= MatchLevel::IgnoreD,
+            _ => self == other,
+        }
+    }
+}
+
+/// Search a query in texts, see `MatchLevel`. Eg: "viet nam" is found in
+/// "Tôi yêu Việt Nam" with `MatchLevel::IgnoreMarks`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Matcher {
+    query: Vec<VChar>,
+    level: MatchLevel,
+}
+
+impl Matcher {
+    pub fn new(query: &str, level: MatchLevel) -> Matcher {
+        Matcher {
+            query: query.chars().map(|c| VChar::from_char(c).0).collect(),
+            level: level,
+        }
+    }
+/// Return the byte ranges of `text` which match the query, from left to
+/// right and without overlap, eg: to highlight them.
+    pub fn find_all(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ret = Vec::new();
+        if self.query.is_empty() {
+            return ret;
+        }
+        let chars: Vec<(usize, VChar)> = text.char_indices()
+            .map(|(i, c)| (i, VChar::from_char(c).0)).collect();
+        let n = self.query.len();
+        let mut i = 0;
+        while i + n <= chars.len() {
+            if self.query.iter().zip(&chars[i..i+n]).all(|(x, y)| x.matches(&y.1, &self.level)) {
+                let end = chars.get(i+n).map_or(text.len(), |x| x.0);
+                ret.push((chars[i].0, end));
+                i += n;
+            } else {
+                i += 1;
+            }
+        }
+        ret
+    }
+    pub fn is_match(&self, text: &str) -> bool {
+        !self.find_all(text).is_empty()
+    }
+}
+
+#[test]
+fn test_vword_normalization() {
+    fn test(nfc: &str, nfd: &str, windows: &str) {
+        for s in &[nfc, nfd, windows] {
+            let x = VWord::from_str(s);
+            assert_eq!((s, x.to_string()), (s, nfc.to_owned()));
+            assert_eq!((s, x.render(&Normalization::Nfc)), (s, nfc.to_owned()));
+            assert_eq!((s, x.render(&Normalization::Nfd)), (s, nfd.to_owned()));
+            assert_eq!((s, x.render(&Normalization::Windows1258)), (s, windows.to_owned()));
+        }
+    }
+    test("Tiếng Việt", "Tie\u{302}\u{301}ng Vie\u{323}\u{302}t", "Tiê\u{301}ng Viê\u{323}t");
+    test("ĐƯỜNG", "ĐU\u{31B}O\u{31B}\u{300}NG", "ĐƯƠ\u{300}NG");
+    test("hợp tác", "ho\u{31B}\u{323}p ta\u{301}c", "hơ\u{323}p ta\u{301}c");
+    test("ặ ẩ ỷ", "a\u{323}\u{306} a\u{302}\u{309} y\u{309}", "ă\u{323} â\u{309} y\u{309}");
+    test("x", "x", "x");
+
+    // Marks in another order, or on a vovel with a tone already
+    assert_eq!(VWord::from_str("e\u{301}\u{302}").to_string(), "ế");
+    assert_eq!(VWord::from_str("o\u{323}\u{302}").to_string(), "ộ");
+    // Marks which do not belong to a Vietnamese vovel stay as they are
+    assert_eq!(VWord::from_str("i\u{302}").vchars()[1], VChar::Invalid('\u{302}'));
+    assert_eq!(VWord::from_str("á\u{300}").to_string(), "á\u{300}");
+    assert_eq!(VWord::from_str("n\u{303}").to_string(), "n\u{303}");
+}
+#[test]
+fn test_matcher() {
+    fn test(query: &str, level: MatchLevel, text: &str, spans: Vec<(usize, usize)>) {
+        let matcher = Matcher::new(query, level.clone());
+        assert_eq!((query, &level, text, matcher.find_all(text)), (query, &level, text, spans));
+    }
+    let text = "Tôi yêu Việt Nam, viet nam.";
+    test("viet nam", MatchLevel::Exact, text, vec![(22, 30)]);
+    test("viet nam", MatchLevel::IgnoreTone, text, vec![(22, 30)]);
+    test("viet nam", MatchLevel::IgnoreMarks, text, vec![(10, 20), (22, 30)]);
+    test("VIỆT", MatchLevel::IgnoreD, text, vec![(10, 16)]);
+    test("viêt", MatchLevel::IgnoreTone, text, vec![(10, 16)]);
+    test("viêt", MatchLevel::IgnoreMarks, text, vec![(10, 16)]);
+
+    test("tieng", MatchLevel::IgnoreTone, "tiếng", vec![]);
+    test("tiêng", MatchLevel::IgnoreTone, "tiếng", vec![(0, 7)]);
+    test("tieng", MatchLevel::IgnoreMarks, "tiếng tiêng tieng", vec![(0, 7), (8, 14), (15, 20)]);
+    test("tiếng", MatchLevel::IgnoreD, "tieng", vec![]);
+
+    test("duong", MatchLevel::IgnoreMarks, "Đường", vec![]);
+    test("duong", MatchLevel::IgnoreD, "Đường dương", vec![(0, 9), (10, 17)]);
+    test("đuong", MatchLevel::IgnoreD, "dương", vec![]);
+
+    test("aa", MatchLevel::Exact, "aaa", vec![(0, 2)]);
+    test("", MatchLevel::Exact, "aaa", vec![]);
+    assert!(Matcher::new("ha noi", MatchLevel::IgnoreMarks).is_match("Hà Nội"));
+}
 #[test]
 fn test_vchar_to_string() {
     macro_rules! test {
@@ -452,6 +969,29 @@ fn test_vword_toggle_tone() {
     test!("ay", R, "ảy", Set); test!("nguyên", X, "nguyễn", Set);
     test!(".chau", S, ".cháu", Set); test!("gi", F, "gì", Set);
     test!("dxf", S, "dxf", None); test!("có", S, "co", Unset);
+    test!("hoa", F, "hoà", Set); test!("khoe", R, "khoẻ", Set);
+    test!("huynh", F, "huỳnh", Set); test!("khuyu", R, "khuỷu", Set);
+    test!("ngoai", F, "ngoài", Set); test!("hoan", F, "hoàn", Set);
+    test!("xoong", S, "xoóng", Set);
+}
+#[test]
+fn test_vword_toggle_tone_with() {
+    macro_rules! test {
+        ( $p:ident $x:expr, $y:ident, $z:expr ) => {
+            {
+                let mut tmp = VWord::from_str($x);
+                assert_eq!(tmp.toggle_tone_with(&Tone::$y, &TonePlacement::$p), VResult::Set);
+                assert_eq!(tmp.to_string(), $z);
+            }
+        };
+        ( $x:expr, $y:ident, $z:expr ) => { test!(New $x, $y, $z) };
+    }
+    test!(Old "hoa", F, "hòa"); test!(Old "khoe", R, "khỏe"); test!(Old "thuy", S, "thúy");
+    test!(Old "hoan", F, "hoàn"); test!(Old "huynh", F, "huỳnh");
+    test!("hoa", F, "hoà"); test!("khoe", R, "khoẻ"); test!("thuy", S, "thuý");
+    test!("hoan", F, "hoàn"); test!("huynh", F, "huỳnh"); test!("ngoai", F, "ngoài");
+    test!("quy", S, "quý"); test!("mua", F, "mùa"); test!("chao", F, "chào");
+    test!("xoong", S, "xoóng"); test!("mooc", J, "moọc");
 }
 #[test]
 fn test_vword_toggle() {
@@ -477,4 +1017,71 @@ fn test_vword_toggle() {
     test!(v O, W, "ố", "ớ", Set); test!(v O, D, "ồ", "ò", Unset);
     test!(v U, W, "u", "ư", Set); test!(v U, W, "ư", "u", Unset);
     test!(v O, W, "o", "ơ", Set); test!(v O, W, "ơ", "o", Unset);
+    // 'uo' cluster
+    test!(v O, W, "truong", "trương", Set); test!(v U, W, "huong", "hương", Set);
+    test!(v O, W, "nguoi", "ngươi", Set); test!(v O, W, "hươu", "huou", Unset);
+    test!(v O, W, "trưong", "trương", Set); test!(v O, W, "truơng", "trương", Set);
+    test!(v O, W, "muôn", "mươn", Set); test!(v O, W, "ưo", "ươ", Set);
+    test!(v O, W, "thuo", "thuơ", Set); test!(v O, W, "thuơ", "thuo", Unset);
+    test!(v U, W, "huo", "huơ", Set); test!(v O, W, "quo", "quơ", Set);
+    // 'o' of 'oa', 'oe' is a glide
+    test!(v A, W, "hoac", "hoăc", Set); test!(v O, W, "hoac", "hoac", None);
+    test!(v O, W, "khoe", "khoe", None);
+}
+#[test]
+fn test_vword_replace_tone() {
+    macro_rules! test {
+        ( $x:expr, $z:expr ) => {
+            {
+                let mut tmp = VWord::from_str($x);
+                tmp.fix_uo();
+                tmp.replace_tone(None);
+                assert_eq!(tmp.to_string(), $z);
+            }
+        };
+    }
+    test!("hóan", "hoán"); test!("tủyên", "tuyển");
+    test!("thuờng", "thường"); test!("thuơng", "thương");
+    test!("thuở", "thuở"); test!("quờ", "quờ");
+    test!("ngừơi", "người"); test!("chào", "chào");
+    test!("xyz", "xyz"); test!("xóong", "xoóng");
+    assert_eq!(VWord::from_str("ĐƯỜNG").tone(), Tone::F);
+}
+#[test]
+fn test_vword_remove() {
+    macro_rules! test {
+        ( $f:ident, $x:expr, $z:expr, $r:ident ) => {
+            {
+                let mut tmp = VWord::from_str($x);
+                assert_eq!(($x, tmp.$f()), ($x, VResult::$r));
+                assert_eq!(tmp.to_string(), $z);
+            }
+        };
+    }
+    test!(remove_tone, "Đường", "Đương", Set); test!(remove_tone, "hoà", "hoa", Set);
+    test!(remove_tone, "đương", "đương", None); test!(remove_tone, "xyz", "xyz", None);
+    test!(remove_diacritics, "Đường", "Duong", Set); test!(remove_diacritics, "ăn", "an", Set);
+    test!(remove_diacritics, "đi", "di", Set); test!(remove_diacritics, "an", "an", None);
+}
+#[test]
+fn test_vword_viqr() {
+    macro_rules! test {
+        ( $x:expr, $y:expr ) => {
+            {
+                assert_eq!(VWord::from_viqr($x).to_string(), $y);
+                assert_eq!(VWord::from_str($y).to_viqr(), $x);
+            }
+        };
+    }
+    test!("Vie^.t Nam", "Việt Nam"); test!("DDa^y", "Đây");
+    test!("tie^'ng", "tiếng"); test!("hu+o+'ng", "hướng");
+    test!("a(n", "ăn"); test!("O+`", "Ờ");
+    test!("Kho^ng?", "Không?"); test!("Ca\\?", "Ca?"); test!("a.\\.", "ạ.");
+    test!("ad\\d", "add"); test!("a\\\\b", "a\\b");
+    test!("Hello!", "Hello!");
+
+    // Marks which can not modify anything are literal
+    assert_eq!(VWord::from_viqr("Nam?").to_string(), "Nam?");
+    assert_eq!(VWord::from_viqr("1+1").to_string(), "1+1");
+    assert_eq!(VWord::from_viqr("i^").to_string(), "i^");
 }
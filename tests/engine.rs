@@ -0,0 +1,142 @@
+extern crate vntyper;
+
+use vntyper::engine::{ Engine, Event };
+use vntyper::input_method::InputMethod;
+use vntyper::util::Validation;
+
+// Feed every character of `keys`, '<' stands for a backspace.
+fn type_keys(engine: &mut Engine, keys: &str) -> String {
+    let mut text = String::new();
+    for c in keys.chars() {
+        let event = if c == '<' { Event::Backspace } else { Event::Key(c) };
+        let update = engine.feed(event);
+        text.push_str(&update.commit);
+    }
+    text
+}
+
+#[test]
+fn preedit() {
+    let mut engine = Engine::new(InputMethod::telex());
+    let preedits: Vec<String> = "chaof".chars()
+        .map(|c| engine.feed(Event::Key(c)).preedit).collect();
+    assert_eq!(preedits, vec!["c", "ch", "cha", "chao", "chào"]);
+    assert_eq!(engine.keys(), &['c', 'h', 'a', 'o', 'f']);
+
+    let update = engine.feed(Event::Commit);
+    assert_eq!((update.commit.as_str(), update.preedit.as_str()), ("chào", ""));
+    assert!(engine.keys().is_empty());
+}
+#[test]
+fn sentence() {
+    let mut engine = Engine::new(InputMethod::telex());
+    assert_eq!(type_keys(&mut engine, "Xin chaof cacs banj, "), "Xin chào các bạn, ");
+    assert_eq!(type_keys(&mut engine, "Tieengs Vieetj."), "Tiếng Việt.");
+
+    let mut engine = Engine::new(InputMethod::vni());
+    assert_eq!(type_keys(&mut engine, "Tie6ng1 Vie6t5 "), "Tiếng Việt ");
+
+    let mut engine = Engine::new(InputMethod::viqr());
+    assert_eq!(type_keys(&mut engine, "Tie^'ng Vie^.t! "), "Tiếng Việt! ");
+}
+#[test]
+fn backspace() {
+    let mut engine = Engine::new(InputMethod::telex());
+    type_keys(&mut engine, "chaof<");
+    assert_eq!(engine.preedit(), "chao");
+    type_keys(&mut engine, "<<s");
+    assert_eq!(engine.preedit(), "chs");
+    type_keys(&mut engine, "<aas");
+    assert_eq!(engine.preedit(), "chấ");
+    type_keys(&mut engine, "<<<<<");
+    assert_eq!(engine.preedit(), "");
+    assert!(!engine.feed(Event::Backspace).handled);
+}
+#[test]
+fn insert_uw() {
+    let mut engine = Engine::new(InputMethod::telex());
+    type_keys(&mut engine, "nhw");
+    assert_eq!(engine.preedit(), "như");
+    type_keys(&mut engine, "w");
+    assert_eq!(engine.preedit(), "nhw");
+    type_keys(&mut engine, "<");
+    assert_eq!(engine.preedit(), "như");
+    type_keys(&mut engine, "w");
+    assert_eq!(engine.preedit(), "nhw");
+    assert_eq!(type_keys(&mut engine, " thuww "), "nhw thuw ");
+}
+#[test]
+fn reset() {
+    let mut engine = Engine::new(InputMethod::telex());
+    type_keys(&mut engine, "chaof");
+    let update = engine.feed(Event::Reset);
+    assert_eq!((update.commit.as_str(), update.preedit.as_str()), ("", ""));
+    assert!(engine.keys().is_empty());
+}
+#[test]
+fn edit() {
+    use vntyper::edit::{ Edit, Unit };
+
+    let mut engine = Engine::new(InputMethod::telex());
+    let mut text = String::new();
+    let mut edits = Vec::new();
+    for c in "chaof ".chars() {
+        let edit = engine.feed_edit(Event::Key(c), &Unit::Scalar).unwrap();
+        edit.apply(&mut text, &Unit::Scalar);
+        edits.push((edit.backspaces, edit.insert));
+    }
+    assert_eq!(text, "chào ");
+    assert_eq!(edits, vec![
+        (0, "c".to_owned()), (0, "h".to_owned()), (0, "a".to_owned()),
+        (0, "o".to_owned()), (2, "ào".to_owned()), (0, " ".to_owned()),
+    ]);
+
+    type_keys(&mut engine, "chaof");
+    assert_eq!(engine.feed_edit(Event::Backspace, &Unit::Grapheme),
+               Some(Edit { backspaces: 2, insert: "ao".to_owned() }));
+    engine.feed(Event::Reset);
+    assert_eq!(engine.feed_edit(Event::Backspace, &Unit::Grapheme), None);
+}
+#[test]
+fn auto_restore() {
+    let mut method = InputMethod::telex();
+    method.options_mut().auto_restore = true;
+    method.options_mut().english_words.insert("mix".to_owned());
+    let mut engine = Engine::new(method);
+    assert_eq!(type_keys(&mut engine, "windows "), "windows ");
+    assert_eq!(type_keys(&mut engine, "What? "), "What? ");
+    assert_eq!(type_keys(&mut engine, "Mix, "), "Mix, ");
+    assert_eq!(type_keys(&mut engine, "Xin chaof cacs banj. "), "Xin chào các bạn. ");
+    type_keys(&mut engine, "Wiki");
+    assert_eq!(engine.feed(Event::Commit).commit, "Wiki");
+
+    // Escapes are kept, unless the word has other changes
+    assert_eq!(type_keys(&mut engine, "chauss tuww ww "), "chaus tuw w ");
+    assert_eq!(type_keys(&mut engine, "address "), "address ");
+
+    // Off by default
+    let mut engine = Engine::new(InputMethod::telex());
+    assert_eq!(type_keys(&mut engine, "windows mix "), "ưindows mĩ ");
+
+    // Words which only break the strict rules
+    let mut method = InputMethod::telex();
+    method.options_mut().auto_restore = true;
+    let mut engine = Engine::new(method.clone());
+    assert_eq!(type_keys(&mut engine, "kas mats "), "ká mát ");
+    method.options_mut().validation = Validation::Strict;
+    let mut engine = Engine::new(method);
+    assert_eq!(type_keys(&mut engine, "kas mats cafc "), "kas mát cafc ");
+}
+#[test]
+fn free_tone() {
+    fn test(keys: &str, s: &str) {
+        let mut engine = Engine::new(InputMethod::telex());
+        type_keys(&mut engine, keys);
+        assert_eq!((keys, engine.preedit()), (keys, s));
+    }
+    test("tuyeenr", "tuyển"); test("tuyreen", "tuyển"); test("turyeen", "tuyển");
+    test("thuowngf", "thường"); test("thuofwng", "thường"); test("thuownfg", "thường");
+    test("nguoiwf", "người"); test("nguofiw", "người");
+    test("hoasn", "hoán"); test("thuowr", "thuở"); test("quowr", "quở");
+    test("Dduwowngf", "Đường");
+}
@@ -22,7 +22,147 @@ fn vietnamese() {
     test_ok("chau", 's', "cháu"); test_ok("sương", 's', "sướng");
     test_ok("dm nha", 's', "dm nhá"); test_ok(".chau", 's', ".cháu");
     test_ok("giao", 's', "giáo"); test_ok("gi", 'f', "gì");
-    test_err("cháu", 's', "chau"); test_err("cháu!", 's', "cháu!");
+    test_ok("cháu", 's', "chaus"); test_err("cháu!", 's', "cháu!");
 
     test_ok("u", 'w', "ư"); test_ok("hôi", 'j', "hội");
+
+    test_ok("truong", 'w', "trương"); test_ok("huong", 'w', "hương");
+    test_ok("nguoi", 'w', "ngươi"); test_ok("thuo", 'w', "thuơ");
+    test_ok("thuơ", 'r', "thuở"); test_ok("hoac", 'w', "hoăc");
+    test_ok("hoăc", 'j', "hoặc");
+
+    test_ok("cháu", 'z', "chau"); test_ok("Đường", 'z', "Đương");
+    test_err("chau", 'z', "chau");
+}
+#[test]
+fn remove_all() {
+    use vntyper::input_method::KeyType;
+
+    let mut method = InputMethod::telex();
+    method.bind('q', KeyType::RemoveAll);
+    let input = Input::new("Đường".to_owned(), 'q', method.clone());
+    assert_eq!(input.output(), Ok("Duong".to_owned()));
+    let input = Input::new("duong".to_owned(), 'q', method);
+    assert_eq!(input.output(), Err("duong".to_owned()));
+}
+#[test]
+fn several_keys() {
+    use vntyper::input_method::KeyType;
+    use vntyper::vword::{ Raw, Flag };
+
+    let mut method = InputMethod::telex();
+    method.merge(&InputMethod::vni());
+    method.bind('[', KeyType::Toggle(Raw::U, Flag::W));
+    method.bind(']', KeyType::Toggle(Raw::O, Flag::W));
+    let test_ok = |a: &str, b: char, c: &str| {
+        let input = Input::new(a.to_owned(), b, method.clone());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    };
+    test_ok("chau", 's', "cháu"); test_ok("chau", '1', "cháu");
+    test_ok("tu", '[', "tư"); test_ok("tu", 'w', "tư");
+    test_ok("co", ']', "cơ"); test_ok("co", '7', "cơ");
+    test_ok("dang", 'd', "đang"); test_ok("dang", '9', "đang");
+}
+#[test]
+fn no_spell_check() {
+    let mut method = InputMethod::telex();
+    method.options_mut().spell_check = false;
+    let input = Input::new("what".to_owned(), 's', method);
+    assert_eq!(input.output(), Ok("whát".to_owned()));
+}
+#[test]
+fn insert_uw() {
+    fn test_ok(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::telex());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    }
+    fn test_err(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::telex());
+        assert_eq!(input.output(), Err(c.to_owned()));
+    }
+    test_ok("", 'w', "ư"); test_ok("t", 'w', "tư");
+    test_ok("nh", 'w', "như"); test_ok("xin ch", 'w', "xin chư");
+    test_ok("", 'W', "Ư"); test_ok("NH", 'W', "NHƯ");
+    test_ok("Nh", 'w', "Như");
+
+    // An 'ư' is a 'u' with a horn here, the engine knows if it was inserted
+    test_ok("ư", 'w', "uw"); test_ok("như", 'w', "nhuw");
+    test_ok("NHƯ", 'W', "NHUW");
+
+    // Not without the option, nor after a vovel
+    test_err("hi", 'w', "hi");
+    let input = Input::new("nh".to_owned(), 'w', InputMethod::new(
+        ['a', 'w', 'e', 'o', 'w', 'w', 'd', 's', 'f', 'r', 'x', 'j']
+    ));
+    assert_eq!(input.output(), Err("nh".to_owned()));
+}
+#[test]
+fn escape() {
+    fn test_ok(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::telex());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    }
+    fn test_err(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::telex());
+        assert_eq!(input.output(), Err(c.to_owned()));
+    }
+    // The second press is typed literally
+    test_ok("cháu", 's', "chaus"); test_ok("CHÁU", 'S', "CHAUS");
+    test_ok("â", 'a', "aa"); test_ok("đ", 'd', "dd");
+    test_ok("cơ", 'w', "cow"); test_err("ca", 'z', "ca");
+
+    // Then the word is left as is
+    test_err("chaus", 'f', "chaus"); test_err("chaus", 's', "chaus");
+    test_err("caa", 'a', "caa"); test_err("dd", 'd', "dd");
+    test_ok("dd nha", 's', "dd nhá");
+    test_ok("chaus nha", 's', "chaus nhá");
+
+    // Vietnamese words are not escaped
+    test_ok("tiêng", 's', "tiếng"); test_ok("đương", 'f', "đường");
+    test_ok("xa", 's', "xá"); test_ok("sao", 'f', "sào");
+    test_ok("khoe", 'r', "khoẻ"); test_ok("rôi", 'f', "rồi");
+    test_ok("xoong", 's', "xoóng"); test_ok("mooc", 'j', "moọc");
+    test_ok("XOONG", 'S', "XOÓNG"); test_err("xoo", 'n', "xoo");
+    test_ok("giooc", 's', "gioóc");
+    test_err("boon", 's', "boon");
+}
+#[test]
+fn decomposed() {
+    fn test(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::telex());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    }
+    test("Vie\u{302}t", 'j', "Việt"); test("tie\u{302}\u{301}ng", 's', "tiêngs");
+    test("đu\u{31B}o\u{31B}ng", 'f', "đường");
+}
+#[test]
+fn tone_placement() {
+    use vntyper::vword::TonePlacement;
+
+    let mut method = InputMethod::telex();
+    let test = |method: &InputMethod, a: &str, b: char, c: &str| {
+        let input = Input::new(a.to_owned(), b, method.clone());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    };
+    test(&method, "hoa", 'f', "hoà"); test(&method, "thuy", 's', "thúy");
+    method.options_mut().tone_placement = Some(TonePlacement::Old);
+    test(&method, "hoa", 'f', "hòa"); test(&method, "khoe", 'r', "khỏe");
+    method.options_mut().tone_placement = Some(TonePlacement::New);
+    test(&method, "hoa", 'f', "hoà"); test(&method, "thuy", 's', "thuý");
+    test(&method, "khoe", 'r', "khoẻ"); test(&method, "hoan", 'f', "hoàn");
+}
+#[test]
+fn check() {
+    use vntyper::util::SpellingError;
+
+    let check = |a: &str| Input::new(a.to_owned(), 's', InputMethod::telex()).check();
+    assert_eq!(check("Xin chao"), Ok(()));
+    assert_eq!(check("a string"), Err(SpellingError::Initial("str".to_owned())));
+    assert_eq!(check("hal"), Err(SpellingError::Final("l".to_owned())));
+    assert_eq!(check("banana"), Err(SpellingError::Groups(6)));
+    assert_eq!(check("Jo"), Err(SpellingError::Character(0, 'j')));
+
+    let mut method = InputMethod::telex();
+    method.options_mut().spell_check = false;
+    assert_eq!(Input::new("hal".to_owned(), 's', method).check(), Ok(()));
 }
@@ -0,0 +1,87 @@
+extern crate vntyper;
+
+use vntyper::engine::compose;
+use vntyper::encoding::Encoding;
+use vntyper::input::Input;
+use vntyper::input_method::InputMethod;
+use vntyper::keystrokes::{ keystrokes, ToneStyle };
+use vntyper::util;
+use vntyper::vword::{ VWord, TonePlacement };
+
+// Return `method` placing tones in the old style, as `util::syllables` does.
+fn old_style(mut method: InputMethod) -> InputMethod {
+    method.options_mut().tone_placement = Some(TonePlacement::Old);
+    method
+}
+
+// Return every 50th syllable. Every syllable is checked by the ignored
+// tests, with `cargo test -- --ignored`.
+fn sample() -> Vec<VWord> {
+    util::syllables().into_iter().enumerate()
+        .filter(|&(i, _)| i % 50 == 0).map(|(_, x)| x).collect()
+}
+
+// Check that every syllable is Vietnamese and is typed with Telex through
+// `Input`, one key at a time.
+fn check_input(syllables: &[VWord]) {
+    let method = old_style(InputMethod::telex());
+    for x in syllables {
+        assert!(util::is_vietnamese(x), "{}", x);
+        let x = x.to_string();
+        let typed = keystrokes(&x, &method, &ToneStyle::EndOfWord).chars()
+            .fold(String::new(), |word, c| {
+                match Input::new(word, c, method.clone()).output() {
+                    Ok(s) => s,
+                    Err(s) => s + &c.to_string(),
+                }
+            });
+        assert_eq!(typed, x);
+    }
+}
+// Check that the keystrokes of every syllable compose it, in Telex and VNI.
+fn check_keystrokes(syllables: &[VWord]) {
+    for method in &[old_style(InputMethod::telex()), old_style(InputMethod::vni())] {
+        for style in &[ToneStyle::EndOfWord, ToneStyle::AfterVovel] {
+            for x in syllables {
+                let x = x.to_string();
+                let keys: Vec<char> = keystrokes(&x, method, style).chars().collect();
+                assert_eq!(compose(&keys, method), x);
+            }
+        }
+    }
+}
+// Check that every syllable, and its uppercase, goes through every encoding.
+fn check_encoding(syllables: &[VWord]) {
+    for encoding in &Encoding::all() {
+        for x in syllables {
+            for x in &[x.to_string(), x.to_string().to_uppercase()] {
+                let bytes = encoding.encode(x).unwrap();
+                assert_eq!((encoding, encoding.decode(&bytes).unwrap()), (encoding, x.clone()));
+            }
+        }
+    }
+}
+
+#[test]
+fn syllables() {
+    let syllables = sample();
+    assert!(syllables.len() * 50 > 18000);
+    check_input(&syllables);
+    check_keystrokes(&syllables);
+    check_encoding(&syllables);
+}
+#[test]
+#[ignore]
+fn every_syllable() {
+    check_input(&util::syllables());
+}
+#[test]
+#[ignore]
+fn every_syllable_keystrokes() {
+    check_keystrokes(&util::syllables());
+}
+#[test]
+#[ignore]
+fn every_syllable_encoding() {
+    check_encoding(&util::syllables());
+}
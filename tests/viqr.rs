@@ -0,0 +1,37 @@
+extern crate vntyper;
+
+use vntyper::input::Input;
+use vntyper::input_method::InputMethod;
+
+#[test]
+fn non_vietnamese() {
+    let input = Input::new("what".to_owned(), '\'', InputMethod::viqr());
+    let output = input.output();
+    assert_eq!(output, Err("what".to_owned()));
+}
+#[test]
+fn vietnamese() {
+    fn test_ok(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::viqr());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    }
+    fn test_err(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::viqr());
+        assert_eq!(input.output(), Err(c.to_owned()));
+    }
+    // Tones
+    test_ok("chau", '\'', "cháu"); test_ok("chau", '`', "chàu");
+    test_ok("ca", '?', "cả"); test_ok("nguyên", '~', "nguyễn");
+    test_ok("hôi", '.', "hội"); test_ok("dm nha", '\'', "dm nhá");
+    test_ok(".chau", '\'', ".cháu"); test_ok("gi", '`', "gì");
+    test_ok("cháu", '\'', "chau'"); test_err("cháu!", '\'', "cháu!");
+
+    // Marks
+    test_ok("a", '^', "â"); test_ok("tie", '^', "tiê");
+    test_ok("ca", '(', "că"); test_ok("co", '+', "cơ");
+    test_ok("u", '+', "ư"); test_ok("dang", 'd', "đang");
+    test_ok("â", '^', "a^");
+
+    // A literal mark stays in its word
+    test_err("cha'", '.', "cha'"); test_err("a(", '\'', "a(");
+}
@@ -0,0 +1,41 @@
+extern crate vntyper;
+
+use vntyper::input::Input;
+use vntyper::input_method::InputMethod;
+
+#[test]
+fn non_vietnamese() {
+    let input = Input::new("what".to_owned(), '1', InputMethod::vni());
+    let output = input.output();
+    assert_eq!(output, Err("what".to_owned()));
+}
+#[test]
+fn vietnamese() {
+    fn test_ok(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::vni());
+        assert_eq!(input.output(), Ok(c.to_owned()));
+    }
+    fn test_err(a: &str, b: char, c: &str) {
+        let input = Input::new(a.to_owned(), b, InputMethod::vni());
+        assert_eq!(input.output(), Err(c.to_owned()));
+    }
+    // Tones
+    test_ok("chau", '1', "cháu"); test_ok("sương", '1', "sướng");
+    test_ok("dm nha", '1', "dm nhá"); test_ok(".chau", '1', ".cháu");
+    test_ok("giao", '1', "giáo"); test_ok("gi", '2', "gì");
+    test_ok("ca", '3', "cả"); test_ok("nguyên", '4', "nguyễn");
+    test_ok("hôi", '5', "hội"); test_ok("CHAU", '2', "CHÀU");
+    test_ok("cháu", '1', "chau1"); test_err("cháu!", '1', "cháu!");
+
+    // Marks
+    test_ok("a", '6', "â"); test_ok("tiên", '1', "tiến");
+    test_ok("tie", '6', "tiê"); test_ok("cho", '6', "chô");
+    test_ok("ca", '8', "că"); test_ok("co", '7', "cơ");
+    test_ok("u", '7', "ư"); test_ok("tu", '7', "tư");
+    test_ok("dang", '9', "đang"); test_ok("Dang", '9', "Đang");
+    test_ok("â", '6', "a6"); test_ok("ư", '7', "u7");
+    test_ok("đang", '9', "dang9");
+
+    // Remove tone
+    test_ok("cháu", '0', "chau"); test_err("chau", '0', "chau");
+}